@@ -1,13 +1,57 @@
-import { ASSOCIATED_TOKEN_PROGRAM_ID, createAssociatedTokenAccount, createMint, TOKEN_PROGRAM_ID } from "@solana/spl-token";
-import { Connection, Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, Transaction, TransactionInstruction, TransactionMessage, VersionedTransaction, type ParsedAccountData } from "@solana/web3.js";
+import { ASSOCIATED_TOKEN_PROGRAM_ID, burn, closeAccount, createAssociatedTokenAccount, createMint, mintTo, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, transfer } from "@solana/spl-token";
+import { ComputeBudgetProgram, Connection, Keypair, LAMPORTS_PER_SOL, PublicKey, SYSVAR_INSTRUCTIONS_PUBKEY, SystemProgram, Transaction, TransactionInstruction, TransactionMessage, VersionedTransaction, type AccountMeta, type ParsedAccountData } from "@solana/web3.js";
 import {describe, expect, it} from  "bun:test";
 import { createNft, fetchMetadataFromSeeds, mplTokenMetadata, verifyCollectionV1 } from "@metaplex-foundation/mpl-token-metadata";
-import { generateSigner, keypairIdentity, percentAmount, type KeypairSigner } from "@metaplex-foundation/umi";
+import { generateSigner, keypairIdentity, percentAmount, publicKey, unwrapOption, type KeypairSigner } from "@metaplex-foundation/umi";
 import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
-import { Stake, StakeDetails } from "./utils";
+import { FundingMode, ProtocolConfig, REWARD_RATE_SCALE, Stake, StakeDetails, Guild, Staker, VestingEscrow, type BoostTier, type SetBonusTier } from "./utils";
+import { readFileSync } from "fs";
+import { homedir } from "os";
 
 // Replace with your program id
 const PROGRAM_ID = new PublicKey("8bJiirYL3y3Gw1W2dwnpwUf3fyMoQmhF4TwYKEyuZkwt");
+// Replace with the program id of programs/mock-vault, a multisig vault stand-in which signs for its pda by CPI
+const MOCK_VAULT_PROGRAM_ID = new PublicKey("7AVxLoH5Tq8c1PGMuU4W3GNRoq1N5Wu5eWhS1LPqzgwq");
+
+// 1000 reward tokens (base units) per 1 second period
+const REWARD_RATE = 1000n * REWARD_RATE_SCALE;
+const REWARD_PERIOD = 1n;
+
+// unstaking within a minute forfeits half of the rewards to the pool treasury
+const MIN_STAKE_DURATION = 60n;
+const EARLY_UNSTAKE_PENALTY_BPS = 5000;
+const EARLY_UNSTAKE_FLAT_FEE = 0n;
+
+// 1% of every reward payout goes to the protocol treasury
+const PROTOCOL_FEE_BPS = 100;
+const PROTOCOL_TREASURY = Keypair.generate();
+const POOL_CREATION_FEE = BigInt(LAMPORTS_PER_SOL / 100);
+
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// the wallet which deployed the program, it is the only one allowed to create the protocol config
+const UPGRADE_AUTHORITY = Keypair.fromSecretKey(
+    Uint8Array.from(JSON.parse(readFileSync(`${homedir()}/.config/solana/id.json`, "utf-8")))
+);
+
+type PoolConfig = {
+    rewardRate: bigint,
+    rewardPeriod: bigint,
+    minStakeDuration: bigint,
+    earlyUnstakePenaltyBps: number,
+    earlyUnstakeFlatFee: bigint,
+    unbondingPeriod: bigint,
+};
+
+const POOL_CONFIG: PoolConfig = {
+    rewardRate: REWARD_RATE,
+    rewardPeriod: REWARD_PERIOD,
+    minStakeDuration: MIN_STAKE_DURATION,
+    earlyUnstakePenaltyBps: EARLY_UNSTAKE_PENALTY_BPS,
+    earlyUnstakeFlatFee: EARLY_UNSTAKE_FLAT_FEE,
+    unbondingPeriod: 0n,
+};
 
 describe("nft-staking", async()=>{
 
@@ -26,6 +70,7 @@ describe("nft-staking", async()=>{
     let stakeDetailsKey: PublicKey|null = null;
     let rewardAccount: PublicKey | null = null;
     let nftMintAccount: PublicKey | null = null;
+    let treasuryAccount: PublicKey | null = null;
 
     const umi = createUmi(clusterUrl);
     // convert to Umi compatible keypair
@@ -55,8 +100,8 @@ describe("nft-staking", async()=>{
         return collectionMint;
     }
 
-    const mintNft = async () => {
-        if(!umiMasterNft){
+    const mintNft = async (collection: KeypairSigner|null = umiMasterNft, updateAuthority: PublicKey|null = null) => {
+        if(!collection){
             throw new Error("Master NFT not initialized");
         }
 
@@ -67,11 +112,11 @@ describe("nft-staking", async()=>{
             mint: nftMint,
             name: "NFT",
             uri:"https://solana.com",
-            updateAuthority: umi.identity.publicKey,
+            updateAuthority: updateAuthority ? publicKey(updateAuthority.toBase58()) : umi.identity.publicKey,
             sellerFeeBasisPoints: percentAmount(0),
             collection:{
                 verified:false, 
-                key: umiMasterNft.publicKey
+                key: collection.publicKey
             }
         }).sendAndConfirm(umi, { send: { commitment: "finalized" } });
 
@@ -81,7 +126,7 @@ describe("nft-staking", async()=>{
 
         await verifyCollectionV1(umi, {
             metadata: nftMetaData.publicKey,
-            collectionMint: umiMasterNft.publicKey,
+            collectionMint: collection.publicKey,
             authority: umi.identity,
         }).sendAndConfirm(umi);
 
@@ -99,6 +144,60 @@ describe("nft-staking", async()=>{
         return stakeDetailsAccount;
     }
 
+    // creates a reward mint along with the protocol fee treasury for it
+    const createRewardMint = async (decimals: number) => {
+        const rewardMint = await createMint(connection, keypair, keypair.publicKey, null, decimals);
+        await createAssociatedTokenAccount(connection, keypair, rewardMint, PROTOCOL_TREASURY.publicKey);
+        return rewardMint;
+    }
+
+    const getTokenBalance = async (tokenAccount: PublicKey) => {
+        const data = (await connection.getParsedAccountInfo(tokenAccount)).value?.data as ParsedAccountData;
+        return BigInt(data.parsed.info.tokenAmount.amount);
+    }
+
+    const sendAndConfirm = async (ixs: TransactionInstruction[], signers: Keypair[] = [keypair]) => {
+        // the first signer pays for the transaction
+        const tx = new Transaction().add(...ixs);
+        const sig = await connection.sendTransaction(tx, signers);
+
+        const {blockhash, lastValidBlockHeight} = (await connection.getLatestBlockhash());
+        await connection.confirmTransaction({blockhash, signature:sig, lastValidBlockHeight }, 'finalized');
+
+        return sig;
+    }
+
+
+    it("should init protocol config", async ()=>{
+
+        const protocolConfig = findProtocolConfig();
+        const existing = await connection.getAccountInfo(protocolConfig);
+
+        // the config is a singleton, so it only gets updated when the tests run again on the same validator
+        const ix = existing
+            ? protocolConfigIx(8, UPGRADE_AUTHORITY.publicKey, protocolConfig)
+            : protocolConfigIx(7, UPGRADE_AUTHORITY.publicKey, protocolConfig, findProgramDataAccount());
+
+        await sendAndConfirm([ix], [UPGRADE_AUTHORITY]);
+
+        const config = ProtocolConfig.getDeserialized((await connection.getAccountInfo(protocolConfig))?.data as Buffer);
+
+        expect(config.admin).toBe(UPGRADE_AUTHORITY.publicKey.toBase58());
+        expect(config.treasury).toBe(PROTOCOL_TREASURY.publicKey.toBase58());
+        expect(config.creation_fee).toBe(POOL_CREATION_FEE);
+        expect(config.default_fee_bps).toBe(PROTOCOL_FEE_BPS);
+        expect(config.allowed_token_programs[0]).toBe(TOKEN_PROGRAM_ID.toBase58());
+
+        // nobody else can change it
+        const intruder = Keypair.generate();
+        await expect(sendAndConfirm([protocolConfigIx(8, intruder.publicKey, protocolConfig)], [keypair, intruder])).rejects.toThrow();
+
+        // the reward payouts only support SPL Token reward mints
+        await expect(sendAndConfirm(
+            [protocolConfigIx(8, UPGRADE_AUTHORITY.publicKey, protocolConfig, undefined, [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID])],
+            [UPGRADE_AUTHORITY]
+        )).rejects.toThrow();
+    })
 
     it("should init staking", async ()=>{
 
@@ -106,7 +205,7 @@ describe("nft-staking", async()=>{
         // create an nft with a collection
         // find the stake details account
 
-        const rewardMint = await createMint(connection, keypair, keypair.publicKey, null, 9, );
+        const rewardMint = await createRewardMint(9);
         rewardAccount = rewardMint;
         console.log("Reward Mint Address : ", rewardMint.toBase58());
         const collectionMint = await createNftCollection();
@@ -117,18 +216,16 @@ describe("nft-staking", async()=>{
         const stakeDetailsAccount = getStakeDetailsAccount(keypair.publicKey, collectionMintKey);
         console.log("Stake Details Account : ", stakeDetailsAccount.toBase58());    
 
-        const ix = new TransactionInstruction({
-            keys: [
-                {pubkey: keypair.publicKey, isSigner: true, isWritable: true},
-                {pubkey: rewardMint, isSigner: false, isWritable: true},
-                {pubkey: collectionMintKey, isSigner: false, isWritable: false},
-                {pubkey: stakeDetailsAccount, isSigner: false, isWritable: true},
-                {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
-                {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
-            ],
-            data: Buffer.from([0]),
-            programId: PROGRAM_ID,
-        })
+        const treasuryTokenAccount = findAssociatedTokenAddress(stakeDetailsAccount, rewardMint);
+        treasuryAccount = treasuryTokenAccount;
+        console.log("Treasury Token Account : ", treasuryTokenAccount.toBase58());
+
+        // a negative minimum stake duration is rejected
+        await expect(sendAndConfirm([
+            initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, {...POOL_CONFIG, minStakeDuration: -1n})
+        ])).rejects.toThrow();
+
+        const ix = initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG);
 
 
         const recentBlockhash = (await connection.getLatestBlockhash('confirmed')).blockhash;
@@ -142,12 +239,17 @@ describe("nft-staking", async()=>{
         const tx = new VersionedTransaction(v0Message);
         tx.sign([keypair]);
 
+        const protocolTreasuryBalanceBefore = await connection.getBalance(PROTOCOL_TREASURY.publicKey);
+
         const sig = await connection.sendTransaction(tx, {skipPreflight: true, maxRetries: 10});
         console.log("Init Signature : ", sig);
 
         const {blockhash, lastValidBlockHeight} = (await connection.getLatestBlockhash());
         await connection.confirmTransaction({blockhash, signature:sig, lastValidBlockHeight }, 'finalized');
 
+        const protocolTreasuryBalanceAfter = await connection.getBalance(PROTOCOL_TREASURY.publicKey);
+        expect(BigInt(protocolTreasuryBalanceAfter - protocolTreasuryBalanceBefore)).toBe(POOL_CREATION_FEE);
+
         const accountDetails = await connection.getParsedAccountInfo(stakeDetailsAccount);
         const serialized = accountDetails.value?.data;
 
@@ -160,6 +262,14 @@ describe("nft-staking", async()=>{
         expect(stakeDetails.creator).toBe(keypair.publicKey.toBase58());
         expect(stakeDetails.reward_token_mint).toBe(rewardMint.toBase58());
         expect(stakeDetails.collection_mint).toBe(collectionMint.publicKey);
+        expect(stakeDetails.reward_rate).toBe(REWARD_RATE);
+        expect(stakeDetails.reward_period).toBe(REWARD_PERIOD);
+        expect(stakeDetails.treasury).toBe(treasuryTokenAccount.toBase58());
+        expect(stakeDetails.min_stake_duration).toBe(MIN_STAKE_DURATION);
+        expect(stakeDetails.early_unstake_penalty_bps).toBe(EARLY_UNSTAKE_PENALTY_BPS);
+        expect(stakeDetails.fee_treasury).toBe(findFeeTreasury(rewardMint).toBase58());
+        expect(stakeDetails.protocol_fee_bps).toBe(PROTOCOL_FEE_BPS);
+        expect(stakeDetails.reward_token_program).toBe(TOKEN_PROGRAM_ID.toBase58());
         
     })
 
@@ -195,23 +305,7 @@ describe("nft-staking", async()=>{
         console.log("Stake Token Account : ", stakeTokenAccount.toBase58());
         console.log("User Reward Account : ", userRewardAccount.toBase58());
 
-        const ix = new TransactionInstruction({
-            keys:[
-                {pubkey: keypair.publicKey, isSigner: true, isWritable: true},
-                {pubkey: nftMintAccount, isSigner: false, isWritable: false},
-                {pubkey: nftMetaDataKey, isSigner: false, isWritable: false},
-                {pubkey: userTokenAccount, isSigner: false, isWritable: true},
-                {pubkey: userRewardAccount, isSigner: false, isWritable: true},
-                {pubkey: stakeDetailsKey, isSigner: false, isWritable: false},
-                {pubkey: stakeAccount, isSigner: false, isWritable: true},
-                {pubkey: stakeTokenAccount, isSigner: false, isWritable: true},
-                {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
-                {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
-                {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
-            ],
-            data: Buffer.from([1]),
-            programId: PROGRAM_ID,
-        });
+        const ix = stakeIx(keypair.publicKey, stakeDetailsKey, nftMintAccount, nftMetaDataKey, rewardAccount);
 
         const tx = new Transaction().add(ix);
 
@@ -232,6 +326,21 @@ describe("nft-staking", async()=>{
         expect(stake.nft_mint).toBe(nftMintAccount.toBase58());
         expect(stake.reward_mint).toBe(rewardAccount.toBase58());
         expect(stake.reward_mint_ata).toBe(userRewardAccount.toBase58());
+        expect(stake.staker).toBe(keypair.publicKey.toBase58());
+        expect(stake.receipt_mint).toBe(findReceiptMint(stakeAccount).toBase58());
+        expect(await getTokenBalance(findReceiptAccount(keypair.publicKey, stakeAccount))).toBe(1n);
+
+        // the staking badge is soulbound
+        const badgeAccount = findBadgeAccount(keypair.publicKey, stakeDetailsKey);
+        const stranger = Keypair.generate();
+        const strangerBadgeAccount = await createAssociatedTokenAccount(
+            connection, keypair, findBadgeMint(stakeDetailsKey), stranger.publicKey, undefined, TOKEN_2022_PROGRAM_ID
+        );
+
+        expect(await getTokenBalance(badgeAccount)).toBe(1n);
+        await expect(
+            transfer(connection, keypair, badgeAccount, strangerBadgeAccount, keypair, 1, [], undefined, TOKEN_2022_PROGRAM_ID)
+        ).rejects.toThrow();
 
 
         const stakeTokenAccountData = (await connection.getParsedAccountInfo(stakeTokenAccount)).value?.data as ParsedAccountData;
@@ -244,8 +353,6 @@ describe("nft-staking", async()=>{
             throw new Error("Stake Details Account or Reward Account or NFT Mint not initialized");
         }
 
-        const REWARD_PER_SECOND = 1000;
-
         console.log("Waiting for 1.5 seconds to overcome lockin period");
         await new Promise(resolve => setTimeout(resolve, 1500));
         console.log("Done waiting");
@@ -265,20 +372,9 @@ describe("nft-staking", async()=>{
         }
 
         const stakeAccountBeforeClaimData = Stake.getDeserialized(stakeAccountBeforeClaim.value?.data as Buffer);
+        const feeTreasuryBeforeClaim = await getTokenBalance(findFeeTreasury(rewardAccount));
 
-        let ix = new TransactionInstruction({
-            keys:[
-                {pubkey: keypair.publicKey, isSigner: true, isWritable: false},
-                {pubkey: stakeAccount, isSigner: false, isWritable: true},
-                {pubkey: userRewardAccount, isSigner: false, isWritable: true},
-                {pubkey: rewardAccount, isSigner: false, isWritable: true},
-                {pubkey: stakeDetailsKey, isSigner: false, isWritable: false},
-                {pubkey: nftMintAccount, isSigner: false, isWritable: false},
-                {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
-            ],
-            data: Buffer.from([2]),
-            programId: PROGRAM_ID,
-        });
+        const ix = claimRewardsIx(keypair.publicKey, stakeDetailsKey, nftMintAccount, rewardAccount);
         
         const tx = new Transaction().add(ix);
 
@@ -302,15 +398,20 @@ describe("nft-staking", async()=>{
 
         expect(stakeAccountAfterClaimData.staked_at).toBeGreaterThan(stakeAccountBeforeClaimData.staked_at);    
 
-        const stakedDuration = Number(stakeAccountAfterClaimData.staked_at - stakeAccountBeforeClaimData.staked_at);
-        const expectedRewardAmount = stakedDuration * REWARD_PER_SECOND;
+        const stakedDuration = BigInt(stakeAccountAfterClaimData.staked_at - stakeAccountBeforeClaimData.staked_at);
+        const accrued = stakedDuration * REWARD_RATE + stakeAccountBeforeClaimData.reward_carry;
+        const rewardAmount = accrued / (REWARD_PERIOD * REWARD_RATE_SCALE);
+        const expectedCarry = accrued % (REWARD_PERIOD * REWARD_RATE_SCALE);
+        const expectedFee = rewardAmount * BigInt(PROTOCOL_FEE_BPS) / 10_000n;
 
-        expect(rewardAfterClaimData.parsed.info.tokenAmount.amount).toBe(expectedRewardAmount.toString());
+        expect(rewardAfterClaimData.parsed.info.tokenAmount.amount).toBe((rewardAmount - expectedFee).toString());
+        expect(stakeAccountAfterClaimData.reward_carry).toBe(expectedCarry);
+        expect(await getTokenBalance(findFeeTreasury(rewardAccount)) - feeTreasuryBeforeClaim).toBe(expectedFee);
     })
 
     it("should unstake NFT", async()=>{
-        if(!stakeDetailsKey || !rewardAccount || !nftMintAccount){
-            throw new Error("Stake Details Account or Reward Account or NFT Mint not initialized");
+        if(!stakeDetailsKey || !rewardAccount || !nftMintAccount || !treasuryAccount){
+            throw new Error("Stake Details Account or Reward Account or NFT Mint or Treasury not initialized");
         }
 
         console.log("Waiting for 1.5 seconds to overcome lockin period");
@@ -344,23 +445,10 @@ describe("nft-staking", async()=>{
         }
 
         const rewardBeforeUnStakeData = rewardBeforeUnStake.value?.data as ParsedAccountData;
+        const treasuryBeforeUnStakeData = (await connection.getParsedAccountInfo(treasuryAccount)).value?.data as ParsedAccountData;
 
 
-        let ix = new TransactionInstruction({
-            keys:[
-                {pubkey: keypair.publicKey, isSigner: true, isWritable: true},
-                {pubkey: userNftTokenAccount, isSigner: false, isWritable: true},
-                {pubkey: userRewardAccount, isSigner: false, isWritable: true},
-                {pubkey: stakeDetailsKey, isSigner: false, isWritable: false},
-                {pubkey: stakeAccount, isSigner: false, isWritable: true},
-                {pubkey: stakeTokenAccount, isSigner: false, isWritable: true},
-                {pubkey: nftMintAccount, isSigner: false, isWritable: false},
-                {pubkey: rewardAccount, isSigner: false, isWritable: true},
-                {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
-            ],
-            data: Buffer.from([3]),
-            programId: PROGRAM_ID,
-        });
+        const ix = unstakeIx(keypair.publicKey, stakeDetailsKey, nftMintAccount, rewardAccount);
         
         const tx = new Transaction().add(ix);
 
@@ -395,19 +483,2368 @@ describe("nft-staking", async()=>{
         expect(Number(rewardAfterUnStakeData.parsed.info.tokenAmount.amount)).toBeGreaterThan(Number(rewardBeforeUnStakeData.parsed.info.tokenAmount.amount));
         expect(userNftAccountData.parsed.info.tokenAmount.uiAmount).toBe(1);
         expect(userAccountAfterUnStake.value?.lamports || 0).toBeGreaterThan(expectedUserBalance);
+        expect(await getTokenBalance(findBadgeAccount(keypair.publicKey, stakeDetailsKey))).toBe(0n);
+
+        // unstaked before MIN_STAKE_DURATION, so half of the rewards go to the treasury
+        const treasuryAfterUnStakeData = (await connection.getParsedAccountInfo(treasuryAccount)).value?.data as ParsedAccountData;
+        const userReward = BigInt(rewardAfterUnStakeData.parsed.info.tokenAmount.amount) - BigInt(rewardBeforeUnStakeData.parsed.info.tokenAmount.amount);
+        const penalty = BigInt(treasuryAfterUnStakeData.parsed.info.tokenAmount.amount) - BigInt(treasuryBeforeUnStakeData.parsed.info.tokenAmount.amount);
+
+        expect(penalty).toBeGreaterThan(0n);
+        expect(userReward - penalty).toBeGreaterThanOrEqual(0n);
+        expect(userReward - penalty).toBeLessThanOrEqual(1n);
+
+        // only the creator can collect the penalties out of the pool treasury
+        const intruder = Keypair.generate();
+        await expect(sendAndConfirm([
+            withdrawTreasuryIx(intruder.publicKey, stakeDetailsKey, rewardAccount, userRewardAccount, penalty)
+        ], [keypair, intruder])).rejects.toThrow();
+
+        const treasuryBeforeWithdraw = await getTokenBalance(treasuryAccount);
+        const userRewardBeforeWithdraw = await getTokenBalance(userRewardAccount);
+
+        await sendAndConfirm([withdrawTreasuryIx(keypair.publicKey, stakeDetailsKey, rewardAccount, userRewardAccount, penalty)]);
+
+        expect(await getTokenBalance(treasuryAccount)).toBe(treasuryBeforeWithdraw - penalty);
+        expect(await getTokenBalance(userRewardAccount)).toBe(userRewardBeforeWithdraw + penalty);
 
     })
 
-})
+    it("should unstake NFT in two steps when the pool has an unbonding period", async()=>{
+
+        const UNBONDING_PERIOD = 3n;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([
+            initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, {...POOL_CONFIG, unbondingPeriod: UNBONDING_PERIOD})
+        ]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const userNftTokenAccount = findAssociatedTokenAddress(keypair.publicKey, nftMintKey);
+
+        // the single-shot unstake is disabled for this pool
+        await expect(sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        await sendAndConfirm([requestUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const unbondingStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        expect(BigInt(unbondingStake.unbonding_ends_at)).toBe(BigInt(unbondingStake.staked_at) + UNBONDING_PERIOD);
+
+        // no rewards while unbonding, and the NFT stays in the pool until the cooldown is over
+        await expect(sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+        await expect(sendAndConfirm([withdrawNftIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        // cancel and request again to check the stake resumes
+        await sendAndConfirm([cancelUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey)]);
+
+        const resumedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        expect(BigInt(resumedStake.unbonding_ends_at)).toBe(0n);
+
+        await sendAndConfirm([requestUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        console.log("Waiting for the unbonding period");
+        await new Promise(resolve => setTimeout(resolve, Number(UNBONDING_PERIOD + 1n) * 1000));
+
+        await sendAndConfirm([withdrawNftIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const userNftAccountData = (await connection.getParsedAccountInfo(userNftTokenAccount)).value?.data as ParsedAccountData;
+
+        expect((await connection.getAccountInfo(stakeAccount))).toBeNull();
+        expect(userNftAccountData.parsed.info.tokenAmount.uiAmount).toBe(1);
+    })
+
+    it("should only reward the time staked after a cancelled unstake", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([
+            initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, {...POOL_CONFIG, unbondingPeriod: 60n})
+        ]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = findAssociatedTokenAddress(keypair.publicKey, rewardMint);
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+        await sendAndConfirm([requestUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const unbondingStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([cancelUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey)]);
+
+        // the unbonding window is skipped, it does not count as staked time either
+        const resumedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const unbondingWindow = BigInt(resumedStake.staked_at) - BigInt(unbondingStake.staked_at);
+
+        expect(unbondingWindow).toBeGreaterThan(0n);
+        expect(BigInt(resumedStake.initial_staked_at)).toBe(BigInt(unbondingStake.initial_staked_at) + unbondingWindow);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        const rewardBeforeClaim = await getTokenBalance(userRewardAccount);
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const claimedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = (BigInt(claimedStake.staked_at) - BigInt(resumedStake.staked_at)) * REWARD_RATE / REWARD_RATE_SCALE;
+        const fee = grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n;
+
+        expect(await getTokenBalance(userRewardAccount) - rewardBeforeClaim).toBe(grossReward - fee);
+    })
+
+    it("should pay an extra reward stream out of its vault", async()=>{
+
+        // 10 partner tokens per second, but the vault only holds 5
+        const STREAM_RATE = 10n * REWARD_RATE_SCALE;
+        const VAULT_FUNDING = 5n;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const streamMint = await createMint(connection, keypair, keypair.publicKey, null, 0);
+
+        await sendAndConfirm([
+            addRewardStreamIx(keypair.publicKey, stakeDetails, streamMint, FundingMode.Vault, STREAM_RATE, 1n)
+        ]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        const vault = findAssociatedTokenAddress(stakeDetails, streamMint);
+
+        expect(stakeDetailsData.reward_stream_count).toBe(1);
+        expect(stakeDetailsData.reward_streams[0].vault).toBe(vault.toBase58());
+
+        // a stream in the pool reward token is rejected
+        await expect(sendAndConfirm([
+            addRewardStreamIx(keypair.publicKey, stakeDetails, rewardMint, FundingMode.Vault, STREAM_RATE, 1n)
+        ])).rejects.toThrow();
+
+        await mintTo(connection, keypair, streamMint, vault, keypair, VAULT_FUNDING);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+        const userStreamAccount = await createAssociatedTokenAccount(connection, keypair, streamMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await sendAndConfirm([
+            claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, rewardStreamAccounts(keypair.publicKey, stakeDetailsData))
+        ]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const owed = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * STREAM_RATE / REWARD_RATE_SCALE;
+
+        // the vault is drained and the rest stays owed until it gets topped up
+        expect(owed).toBeGreaterThan(VAULT_FUNDING);
+        expect(await getTokenBalance(userStreamAccount)).toBe(VAULT_FUNDING);
+        expect(await getTokenBalance(vault)).toBe(0n);
+        expect(stakeAfterClaim.stream_carries[0]).toBe((owed - VAULT_FUNDING) * REWARD_RATE_SCALE);
+
+        // the position can not leave while the vault is short, it would lose what it is owed
+        const streamAccounts = rewardStreamAccounts(keypair.publicKey, stakeDetailsData);
+        await expect(sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, streamAccounts)])).rejects.toThrow();
+
+        await mintTo(connection, keypair, streamMint, vault, keypair, 1_000n);
+        await sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, streamAccounts)]);
+
+        expect(await getTokenBalance(userStreamAccount)).toBeGreaterThan(owed);
+        expect((await connection.getAccountInfo(stakeAccount))).toBeNull();
+    })
+
+    it("should pay the reward streams in full before a position unbonds", async()=>{
+
+        const STREAM_RATE = 10n * REWARD_RATE_SCALE;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([
+            initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, {...POOL_CONFIG, unbondingPeriod: 1n})
+        ]);
+
+        const streamMint = await createMint(connection, keypair, keypair.publicKey, null, 0);
+
+        await sendAndConfirm([
+            addRewardStreamIx(keypair.publicKey, stakeDetails, streamMint, FundingMode.Vault, STREAM_RATE, 1n)
+        ]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        const vault = findAssociatedTokenAddress(stakeDetails, streamMint);
+        const streamAccounts = rewardStreamAccounts(keypair.publicKey, stakeDetailsData);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userStreamAccount = await createAssociatedTokenAccount(connection, keypair, streamMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakedPosition = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        // withdrawing pays nothing, so the empty vault must not let the unstake request through
+        await expect(sendAndConfirm([
+            requestUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, streamAccounts)
+        ])).rejects.toThrow();
+
+        await mintTo(connection, keypair, streamMint, vault, keypair, 1_000n);
+        await sendAndConfirm([requestUnstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, streamAccounts)]);
+
+        const unbondingStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const owed = BigInt(unbondingStake.staked_at - stakedPosition.staked_at) * STREAM_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(userStreamAccount)).toBe(owed);
+        expect(unbondingStake.stream_carries[0]).toBe(0n);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([withdrawNftIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        expect((await connection.getAccountInfo(stakeAccount))).toBeNull();
+    })
+
+    it("should move a staked position along with its receipt", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const receiptMint = findReceiptMint(stakeAccount);
+
+        // the pool signs for the reward mint, so only the stake account can mint a receipt
+        const stakedReceiptMintData = (await connection.getParsedAccountInfo(receiptMint)).value?.data as ParsedAccountData;
+        expect(stakedReceiptMintData.parsed.info.mintAuthority).toBe(stakeAccount.toBase58());
+        await expect(sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, receiptMint)])).rejects.toThrow();
+
+        // sell the position to a buyer, who needs some SOL for the fees
+        const buyer = Keypair.generate();
+        await sendAndConfirm([
+            SystemProgram.transfer({fromPubkey: keypair.publicKey, toPubkey: buyer.publicKey, lamports: LAMPORTS_PER_SOL / 10})
+        ]);
+
+        const buyerReceiptAccount = await createAssociatedTokenAccount(connection, keypair, receiptMint, buyer.publicKey);
+        await transfer(connection, keypair, findReceiptAccount(keypair.publicKey, stakeAccount), buyerReceiptAccount, keypair, 1);
+
+        // the staker no longer owns the position
+        await expect(sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        const buyerRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, buyer.publicKey);
+        const buyerNftAccount = await createAssociatedTokenAccount(connection, keypair, nftMintKey, buyer.publicKey);
+
+        await new Promise(resolve => setTimeout(resolve, 1500));
+
+        await sendAndConfirm([
+            claimRewardsIx(buyer.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey)
+        ], [buyer]);
+
+        expect(await getTokenBalance(buyerRewardAccount)).toBeGreaterThan(0n);
+
+        await sendAndConfirm([
+            unstakeIx(buyer.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey)
+        ], [buyer]);
+
+        const receiptMintData = (await connection.getParsedAccountInfo(receiptMint)).value?.data as ParsedAccountData;
+
+        expect(await getTokenBalance(buyerNftAccount)).toBe(1n);
+        expect(receiptMintData.parsed.info.supply).toBe("0");
+        expect(await connection.getAccountInfo(buyerReceiptAccount)).toBeNull();
+        expect(await getTokenBalance(findBadgeAccount(keypair.publicKey, stakeDetails))).toBe(0n);
+        expect(await connection.getAccountInfo(stakeAccount)).toBeNull();
+    })
+
+    it("should unstake after the staker closed its badge account", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // the badge can not be transferred, but its owner can still burn it and close the account
+        const badgeAccount = findBadgeAccount(keypair.publicKey, stakeDetails);
+        await burn(connection, keypair, badgeAccount, findBadgeMint(stakeDetails), keypair, 1, [], undefined, TOKEN_2022_PROGRAM_ID);
+        await closeAccount(connection, keypair, badgeAccount, keypair.publicKey, keypair, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+        await sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        expect(await getTokenBalance(findAssociatedTokenAddress(keypair.publicKey, nftMintKey))).toBe(1n);
+        expect(await connection.getAccountInfo(findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey))).toBeNull();
+    })
+
+    it("should mint milestone NFTs to long-term stakers", async()=>{
+
+        // the metaplex CPIs need more than the default compute budget
+        const computeBudgetIx = ComputeBudgetProgram.setComputeUnitLimit({units: 600_000});
+        const MILESTONES = [
+            {duration: 2n, name: "Staked for 2 seconds", uri: "https://solana.com/2s"},
+            {duration: 86_400n, name: "Staked for a day", uri: "https://solana.com/1d"},
+        ];
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([computeBudgetIx, configureMilestonesIx(keypair.publicKey, stakeDetails, MILESTONES)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+
+        expect(stakeDetailsData.milestone_collection).toBe(findMilestoneCollection(stakeDetails).toBase58());
+        expect(stakeDetailsData.milestones).toEqual(MILESTONES);
+
+        // milestones are configured once
+        await expect(sendAndConfirm([computeBudgetIx, configureMilestonesIx(keypair.publicKey, stakeDetails, MILESTONES)])).rejects.toThrow();
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        const dayMilestoneMint = Keypair.generate();
+        await expect(sendAndConfirm([
+            computeBudgetIx, claimMilestoneIx(keypair.publicKey, stakeDetails, nftMintKey, dayMilestoneMint.publicKey, 1)
+        ], [keypair, dayMilestoneMint])).rejects.toThrow();
+
+        await new Promise(resolve => setTimeout(resolve, 3000));
+
+        const milestoneMint = Keypair.generate();
+        await sendAndConfirm([
+            computeBudgetIx, claimMilestoneIx(keypair.publicKey, stakeDetails, nftMintKey, milestoneMint.publicKey, 0)
+        ], [keypair, milestoneMint]);
+
+        const milestoneMetadata = await fetchMetadataFromSeeds(umi, {mint: publicKey(milestoneMint.publicKey.toBase58())});
+        const stakeData = Stake.getDeserialized((await connection.getAccountInfo(findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey)))?.data as Buffer);
+
+        expect(await getTokenBalance(findAssociatedTokenAddress(keypair.publicKey, milestoneMint.publicKey))).toBe(1n);
+        expect(milestoneMetadata.name).toBe(MILESTONES[0].name);
+        expect(milestoneMetadata.updateAuthority.toString()).toBe(stakeDetails.toBase58());
+        expect(unwrapOption(milestoneMetadata.collection)?.verified).toBe(true);
+        expect(unwrapOption(milestoneMetadata.collection)?.key.toString()).toBe(findMilestoneCollection(stakeDetails).toBase58());
+        expect(stakeData.milestones_claimed).toBe(1);
+
+        // each milestone can be claimed once
+        const secondMint = Keypair.generate();
+        await expect(sendAndConfirm([
+            computeBudgetIx, claimMilestoneIx(keypair.publicKey, stakeDetails, nftMintKey, secondMint.publicKey, 0)
+        ], [keypair, secondMint])).rejects.toThrow();
+    })
+
+    it("should evolve a staked NFT to the next level", async()=>{
+
+        const LEVELS = [
+            {duration: 1n, uri: "https://solana.com/level-1"},
+            {duration: 86_400n, uri: "https://solana.com/level-2"},
+        ];
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureLevelsIx(keypair.publicKey, stakeDetails, LEVELS)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.levels).toEqual(LEVELS);
+
+        // the pool is the update authority of the NFT
+        const nftMint = await mintNft(collection, stakeDetails);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await sendAndConfirm([evolveNftIx(keypair.publicKey, stakeDetails, nftMintKey)]);
+
+        const evolvedMetadata = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const stakeData = Stake.getDeserialized((await connection.getAccountInfo(findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey)))?.data as Buffer);
+
+        expect(evolvedMetadata.uri).toBe(LEVELS[0].uri);
+        expect(evolvedMetadata.name).toBe(nftMetaData.name);
+        expect(stakeData.level).toBe(1);
+
+        // the second level needs a day of staking
+        await expect(sendAndConfirm([evolveNftIx(keypair.publicKey, stakeDetails, nftMintKey)])).rejects.toThrow();
+    })
+
+    it("should pay a set bonus for staking several NFTs of the pool", async()=>{
+
+        // +50% rewards on every NFT from the second one on
+        const TIERS = [{min_staked: 2, bonus_bps: 5000}, {min_staked: 5, bonus_bps: 10000}];
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        // a single NFT is not a set
+        await expect(sendAndConfirm([
+            configureSetBonusIx(keypair.publicKey, stakeDetails, [{min_staked: 1, bonus_bps: 5000}])
+        ])).rejects.toThrow();
+
+        await sendAndConfirm([configureSetBonusIx(keypair.publicKey, stakeDetails, TIERS)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.set_bonus_tiers).toEqual(TIERS);
+
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+        const nftMints: PublicKey[] = [];
+
+        for (let i = 0; i < 2; i++) {
+            const nftMint = await mintNft(collection);
+            const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+            nftMints.push(new PublicKey(nftMint.publicKey.toString()));
+
+            await sendAndConfirm([
+                stakeIx(keypair.publicKey, stakeDetails, nftMints[i], new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+            ]);
+        }
+
+        const stakerAccount = findStakerAccount(stakeDetails, keypair.publicKey);
+        const stakerAfterStake = Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer);
+
+        // the first NFT earned no bonus on its own
+        expect(stakerAfterStake.staked_count).toBe(2);
+        expect(stakerAfterStake.boost_index).toBe(0n);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMints[0], keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMints[0], rewardMint)]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const stakerAfterClaim = Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer);
+
+        const baseReward = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+        const setBonus = stakerAfterClaim.boost_index * REWARD_RATE / REWARD_RATE_SCALE / 10_000n;
+        const grossReward = baseReward + setBonus;
+
+        expect(setBonus).toBeGreaterThan(0n);
+        expect(stakeAfterClaim.boost_index_snapshot).toBe(stakerAfterClaim.boost_index);
+        expect(await getTokenBalance(userRewardAccount)).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+
+        // new tiers only apply to a staker from its next update on, never to the time before
+        await sendAndConfirm([configureSetBonusIx(keypair.publicKey, stakeDetails, [{min_staked: 2, bonus_bps: 10000}])]);
+        await new Promise(resolve => setTimeout(resolve, 1000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMints[0], rewardMint)]);
+
+        const stakerAfterTierChange = Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer);
+        const elapsed = stakerAfterTierChange.last_update - stakerAfterClaim.last_update;
+
+        expect(stakerAfterTierChange.boost_index - stakerAfterClaim.boost_index).toBe(5000n * elapsed);
+        expect(stakerAfterTierChange.set_bonus_bps).toBe(10000);
+
+        // a sold position leaves the set of the seller at the first claim of the buyer
+        const buyer = Keypair.generate();
+        await sendAndConfirm([
+            SystemProgram.transfer({fromPubkey: keypair.publicKey, toPubkey: buyer.publicKey, lamports: LAMPORTS_PER_SOL / 10})
+        ]);
+
+        const soldStakeAccount = findStakeAccount(stakeDetails, nftMints[1], keypair.publicKey);
+        const buyerReceiptAccount = await createAssociatedTokenAccount(connection, keypair, findReceiptMint(soldStakeAccount), buyer.publicKey);
+        await transfer(connection, keypair, findReceiptAccount(keypair.publicKey, soldStakeAccount), buyerReceiptAccount, keypair, 1);
+
+        await sendAndConfirm([claimRewardsIx(buyer.publicKey, stakeDetails, nftMints[1], rewardMint, [], keypair.publicKey)], [buyer]);
+
+        const stakerAfterSale = Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer);
+        const soldStake = Stake.getDeserialized((await connection.getAccountInfo(soldStakeAccount))?.data as Buffer);
+
+        expect(soldStake.left_set).toBe(true);
+        expect(stakerAfterSale.staked_count).toBe(1);
+        expect(stakerAfterSale.set_bonus_bps).toBe(0);
+
+        // the position is not counted twice when it leaves the pool
+        await createAssociatedTokenAccount(connection, keypair, nftMints[1], buyer.publicKey);
+        await sendAndConfirm([unstakeIx(buyer.publicKey, stakeDetails, nftMints[1], rewardMint, [], keypair.publicKey)], [buyer]);
+
+        const stakerAfterUnstake = Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer);
+        expect(stakerAfterUnstake.staked_count).toBe(1);
+    })
+
+    it("should boost a position by locking reward tokens", async()=>{
+
+        // locking 100 reward tokens doubles the rewards
+        const TIERS = [{min_locked: 100n, boost_bps: 10000}];
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureBoostIx(keypair.publicKey, stakeDetails, TIERS)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.boost_tiers).toEqual(TIERS);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // earn some reward tokens to lock
+        await new Promise(resolve => setTimeout(resolve, 1000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const boostVault = findAssociatedTokenAddress(stakeAccount, rewardMint);
+
+        await sendAndConfirm([boostIx(16, keypair.publicKey, stakeDetails, nftMintKey, rewardMint, 100n)]);
+
+        const boostedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const balanceBeforeClaim = await getTokenBalance(userRewardAccount);
+
+        expect(boostedStake.locked_boost).toBe(100n);
+        expect(await getTokenBalance(boostVault)).toBe(100n);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const claimedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = 2n * BigInt(claimedStake.staked_at - boostedStake.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(userRewardAccount) - balanceBeforeClaim).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(claimedStake.boost_bps).toBe(10000);
+
+        // new tiers only apply from the next checkpoint of the position, never to the time before
+        await sendAndConfirm([configureBoostIx(keypair.publicKey, stakeDetails, [{min_locked: 100n, boost_bps: 20000}])]);
+        await new Promise(resolve => setTimeout(resolve, 1000));
+
+        const balanceBeforeTierChange = await getTokenBalance(userRewardAccount);
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const stakeAfterTierChange = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const oldRateReward = 2n * BigInt(stakeAfterTierChange.staked_at - claimedStake.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(userRewardAccount) - balanceBeforeTierChange).toBe(oldRateReward - oldRateReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(stakeAfterTierChange.boost_bps).toBe(20000);
+
+        // more than the locked amount can not be unlocked
+        await expect(sendAndConfirm([boostIx(17, keypair.publicKey, stakeDetails, nftMintKey, rewardMint, 101n)])).rejects.toThrow();
+
+        await sendAndConfirm([boostIx(17, keypair.publicKey, stakeDetails, nftMintKey, rewardMint, 40n)]);
+        expect(await getTokenBalance(boostVault)).toBe(60n);
+
+        // the rest is unlocked on unstake
+        await sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+        expect(await connection.getAccountInfo(boostVault)).toBeNull();
+    })
+
+    it("should boost a position until its potion expires", async()=>{
+
+        // a 2x potion for 2 seconds
+        const POTION_PRICE = 500n;
+        const POTION_BOOST_BPS = 10000;
+        const POTION_DURATION = 2n;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // the pool does not sell potions yet
+        await expect(sendAndConfirm([buyPotionIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        // a potion must last
+        await expect(sendAndConfirm([configurePotionIx(keypair.publicKey, stakeDetails, POTION_PRICE, POTION_BOOST_BPS, 0n)])).rejects.toThrow();
+        await expect(sendAndConfirm([configurePotionIx(keypair.publicKey, stakeDetails, 0n, POTION_BOOST_BPS, -1n)])).rejects.toThrow();
+
+        await sendAndConfirm([configurePotionIx(keypair.publicKey, stakeDetails, POTION_PRICE, POTION_BOOST_BPS, POTION_DURATION)]);
+
+        // earn enough reward tokens to pay for the potion
+        await new Promise(resolve => setTimeout(resolve, 1000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const balanceBeforePotion = await getTokenBalance(userRewardAccount);
+        await sendAndConfirm([buyPotionIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const potionStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(balanceBeforePotion - POTION_PRICE);
+        expect(potionStake.potion_expires_at).toBe(BigInt(potionStake.staked_at) + POTION_DURATION);
+
+        // one potion at a time
+        await expect(sendAndConfirm([buyPotionIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        // claim well after the expiry, only the first 2 seconds are doubled
+        await new Promise(resolve => setTimeout(resolve, 4000));
+
+        const balanceBeforeClaim = await getTokenBalance(userRewardAccount);
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        const claimedStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = (BigInt(claimedStake.staked_at - potionStake.staked_at) + POTION_DURATION) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(userRewardAccount) - balanceBeforeClaim).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+    })
+
+    it("should accrue points in a pool without a reward mint", async()=>{
+
+        // the system program stands in for the reward mint of a points-only pool
+        const pointsMint = SystemProgram.programId;
+
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, pointsMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const stakerAccount = findStakerAccount(stakeDetails, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), pointsMint)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, pointsMint)]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const points = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        // points are not charged the protocol fee
+        expect(Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer).points).toBe(points);
+
+        // there is nothing to convert the points into yet
+        const rewardMint = await createRewardMint(0);
+        await expect(sendAndConfirm([convertPointsIx(keypair.publicKey, stakeDetails, rewardMint, points)])).rejects.toThrow();
+
+        await sendAndConfirm([attachRewardMintIx(keypair.publicKey, stakeDetails, rewardMint)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.reward_token_mint).toBe(rewardMint.toBase58());
+        expect(stakeDetailsData.reward_token_program).toBe(TOKEN_PROGRAM_ID.toBase58());
+
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+        await sendAndConfirm([convertPointsIx(keypair.publicKey, stakeDetails, rewardMint, points)]);
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(points - points * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(Staker.getDeserialized((await connection.getAccountInfo(stakerAccount))?.data as Buffer).points).toBe(0n);
+
+        // the pool mints rewards from now on
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+        expect(await getTokenBalance(userRewardAccount)).toBeGreaterThan(points - points * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+    })
+
+    it("should share deposited SOL among the staked NFTs", async()=>{
+
+        const REVENUE = 1_000_000n;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const revenueVault = findRevenueVault(stakeDetails);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        // nobody could claim a deposit made before the first stake
+        await expect(sendAndConfirm([depositRevenueIx(keypair.publicKey, stakeDetails, REVENUE)])).rejects.toThrow();
+
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+        const nftMintKeys: PublicKey[] = [];
+
+        for (let i = 0; i < 2; i++) {
+            const nftMint = await mintNft(collection);
+            const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+            const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+
+            await sendAndConfirm([
+                stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+            ]);
+
+            nftMintKeys.push(nftMintKey);
+        }
+
+        await sendAndConfirm([depositRevenueIx(keypair.publicKey, stakeDetails, REVENUE)]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.total_staked).toBe(2n);
+        expect(stakeDetailsData.revenue_vault).toBe(revenueVault.toBase58());
+
+        // each NFT takes half of the deposit out of the vault
+        const vaultBalance = BigInt(await connection.getBalance(revenueVault));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKeys[0], rewardMint)]);
+        expect(BigInt(await connection.getBalance(revenueVault))).toBe(vaultBalance - REVENUE / 2n);
+
+        // a second claim has no revenue left to take
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKeys[0], rewardMint)]);
+        expect(BigInt(await connection.getBalance(revenueVault))).toBe(vaultBalance - REVENUE / 2n);
+
+        // half lamports are carried over to the next claim instead of being lost
+        await sendAndConfirm([depositRevenueIx(keypair.publicKey, stakeDetails, 3n)]);
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKeys[0], rewardMint)]);
+        expect(BigInt(await connection.getBalance(revenueVault))).toBe(vaultBalance + 3n - REVENUE / 2n - 1n);
+
+        await sendAndConfirm([depositRevenueIx(keypair.publicKey, stakeDetails, 3n)]);
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKeys[0], rewardMint)]);
+        expect(BigInt(await connection.getBalance(revenueVault))).toBe(vaultBalance + 6n - REVENUE / 2n - 3n);
+
+        await sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKeys[1], rewardMint)]);
+        // the other NFT was paid both deposits at once
+        expect(BigInt(await connection.getBalance(revenueVault))).toBe(vaultBalance - REVENUE);
+
+        const unstakedDetails = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(unstakedDetails.total_staked).toBe(1n);
+    })
+
+
+    it("should vest claimed rewards in an escrow", async()=>{
+
+        const VESTING_PERIOD = 4n;
+        const INSTANT_WITHDRAW_PENALTY_BPS = 5000;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const vestingEscrow = findVestingEscrow(stakeDetails, keypair.publicKey);
+        const treasury = findAssociatedTokenAddress(stakeDetails, rewardMint);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureVestingIx(keypair.publicKey, stakeDetails, VESTING_PERIOD, INSTANT_WITHDRAW_PENALTY_BPS)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // the rewards are locked in the escrow instead of being minted to the user
+        await new Promise(resolve => setTimeout(resolve, 1000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, vestingEscrow)]);
+
+        const escrow = VestingEscrow.getDeserialized((await connection.getAccountInfo(vestingEscrow))?.data as Buffer);
+
+        expect(escrow.locked_amount).toBeGreaterThan(0n);
+        expect(escrow.vesting_ends_at).toBe(escrow.last_update + VESTING_PERIOD);
+        expect(await getTokenBalance(userRewardAccount)).toBe(0n);
+
+        // once fully vested, everything is withdrawn without penalty
+        await new Promise(resolve => setTimeout(resolve, 5000));
+        await sendAndConfirm([withdrawVestedIx(keypair.publicKey, stakeDetails, rewardMint, false)]);
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(escrow.locked_amount);
+
+        // an instant withdrawal forfeits part of the locked rewards to the treasury
+        await new Promise(resolve => setTimeout(resolve, 1000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, vestingEscrow)]);
+
+        const lockedAmount = VestingEscrow.getDeserialized((await connection.getAccountInfo(vestingEscrow))?.data as Buffer).locked_amount;
+        const balanceBefore = await getTokenBalance(userRewardAccount);
+        const treasuryBefore = await getTokenBalance(treasury);
+
+        await sendAndConfirm([withdrawVestedIx(keypair.publicKey, stakeDetails, rewardMint, true)]);
+
+        const withdrawn = await getTokenBalance(userRewardAccount) - balanceBefore;
+        const forfeited = await getTokenBalance(treasury) - treasuryBefore;
+
+        expect(withdrawn + forfeited).toBe(lockedAmount);
+        expect(forfeited).toBeGreaterThan(0n);
+
+        const emptyEscrow = VestingEscrow.getDeserialized((await connection.getAccountInfo(vestingEscrow))?.data as Buffer);
+        expect(emptyEscrow.locked_amount + emptyEscrow.vested_amount).toBe(0n);
+
+        await expect(sendAndConfirm([withdrawVestedIx(keypair.publicKey, stakeDetails, rewardMint, false)])).rejects.toThrow();
+    })
+
+
+    it("should pay the referrer a share of the rewards of the referred staker", async()=>{
+
+        // 10% of the rewards of the referred staker
+        const REFERRAL_BPS = 1000;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureReferralIx(keypair.publicKey, stakeDetails, REFERRAL_BPS)]);
+
+        const referrer = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(referrer.publicKey, LAMPORTS_PER_SOL), "finalized");
+        const referrerRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, referrer.publicKey);
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        // only a wallet which staked in the pool can refer
+        await expect(sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, findStakerAccount(stakeDetails, referrer.publicKey))
+        ])).rejects.toThrow();
+
+        const referrerNftMint = await mintNft(collection);
+        const referrerNftMintKey = new PublicKey(referrerNftMint.publicKey.toString());
+        const referrerNftMetaData = await fetchMetadataFromSeeds(umi, {mint:referrerNftMint.publicKey});
+
+        const referrerNftAccount = await createAssociatedTokenAccount(connection, keypair, referrerNftMintKey, referrer.publicKey);
+        await transfer(connection, keypair, findAssociatedTokenAddress(keypair.publicKey, referrerNftMintKey), referrerNftAccount, keypair, 1);
+        await sendAndConfirm([
+            stakeIx(referrer.publicKey, stakeDetails, referrerNftMintKey, new PublicKey(referrerNftMetaData.publicKey.toString()), rewardMint)
+        ], [referrer]);
+
+        // a staker can not refer itself
+        await expect(sendAndConfirm([
+            stakeIx(referrer.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, findStakerAccount(stakeDetails, referrer.publicKey))
+        ], [referrer])).rejects.toThrow();
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, findStakerAccount(stakeDetails, referrer.publicKey))
+        ]);
+
+        const staker = Staker.getDeserialized((await connection.getAccountInfo(findStakerAccount(stakeDetails, keypair.publicKey)))?.data as Buffer);
+        expect(staker.referrer).toBe(referrer.publicKey.toBase58());
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        // the referrer accounts are required once the staker was referred
+        await expect(sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)])).rejects.toThrow();
+
+        await sendAndConfirm([
+            claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, referralAccounts(referrer.publicKey, rewardMint))
+        ]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        // the referral is paid on top of the rewards of the staker, the protocol fee is taken on it too
+        const referral = grossReward * BigInt(REFERRAL_BPS) / 10_000n;
+        expect(await getTokenBalance(referrerRewardAccount)).toBe(referral - referral * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(await getTokenBalance(userRewardAccount)).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+    })
+
+
+    it("should split the rewards of a guild position among the members", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const guild = findGuildAccount(stakeDetails, keypair.publicKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        // the second member takes 3 times the share of the first one
+        const member = Keypair.generate();
+        await sendAndConfirm([configureGuildIx(keypair.publicKey, stakeDetails, [
+            {wallet: keypair.publicKey, weight: 1},
+            {wallet: member.publicKey, weight: 3},
+        ])]);
+
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+        const memberRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, member.publicKey);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, guild)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        expect(Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer).guild).toBe(guild.toBase58());
+
+        // the rewards of the position are credited to the guild
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, guild)]);
+
+        const guildBalance = Guild.getDeserialized((await connection.getAccountInfo(guild))?.data as Buffer).reward_balance;
+
+        expect(guildBalance).toBeGreaterThan(0n);
+        expect(await getTokenBalance(userRewardAccount)).toBe(0n);
+
+        // the members can not change while the guild holds their rewards
+        await expect(sendAndConfirm([configureGuildIx(keypair.publicKey, stakeDetails, [{wallet: keypair.publicKey, weight: 1}])])).rejects.toThrow();
+
+        await sendAndConfirm([claimGuildRewardsIx(keypair.publicKey, guild, stakeDetails, rewardMint, [keypair.publicKey, member.publicKey])]);
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(guildBalance / 4n);
+        expect(await getTokenBalance(memberRewardAccount)).toBe(guildBalance * 3n / 4n);
+        expect(Guild.getDeserialized((await connection.getAccountInfo(guild))?.data as Buffer).reward_balance).toBe(guildBalance - guildBalance / 4n - guildBalance * 3n / 4n);
+
+        // the rounding remainder does not hold the members back
+        await sendAndConfirm([configureGuildIx(keypair.publicKey, stakeDetails, [{wallet: keypair.publicKey, weight: 1}])]);
+    })
+
+
+    it("should pay the buyer of a guild position instead of the guild", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const guild = findGuildAccount(stakeDetails, keypair.publicKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureGuildIx(keypair.publicKey, stakeDetails, [{wallet: keypair.publicKey, weight: 1}])]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, guild)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+
+        // sell the position to a buyer, who needs some SOL for the fees
+        const buyer = Keypair.generate();
+        await sendAndConfirm([
+            SystemProgram.transfer({fromPubkey: keypair.publicKey, toPubkey: buyer.publicKey, lamports: LAMPORTS_PER_SOL / 10})
+        ]);
+
+        const buyerReceiptAccount = await createAssociatedTokenAccount(connection, keypair, findReceiptMint(stakeAccount), buyer.publicKey);
+        await transfer(connection, keypair, findReceiptAccount(keypair.publicKey, stakeAccount), buyerReceiptAccount, keypair, 1);
+
+        const buyerRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, buyer.publicKey);
+        await createAssociatedTokenAccount(connection, keypair, nftMintKey, buyer.publicKey);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        // the guild of the seller is not paid anymore
+        await expect(sendAndConfirm([
+            claimRewardsIx(buyer.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, guild)
+        ], [buyer])).rejects.toThrow();
+
+        await sendAndConfirm([
+            claimRewardsIx(buyer.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey)
+        ], [buyer]);
+
+        expect(await getTokenBalance(buyerRewardAccount)).toBeGreaterThan(0n);
+        expect(Guild.getDeserialized((await connection.getAccountInfo(guild))?.data as Buffer).reward_balance).toBe(0n);
+        expect(Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer).guild).toBe(PublicKey.default.toBase58());
+
+        await sendAndConfirm([
+            unstakeIx(buyer.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey)
+        ], [buyer]);
+
+        expect(await connection.getAccountInfo(stakeAccount)).toBeNull();
+    })
+
+    it("should vest the rewards of a guild position in a vesting pool", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const guild = findGuildAccount(stakeDetails, keypair.publicKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureVestingIx(keypair.publicKey, stakeDetails, 1000n, 5000)]);
+
+        // a guild of the staker alone can not skip the vesting
+        await sendAndConfirm([configureGuildIx(keypair.publicKey, stakeDetails, [{wallet: keypair.publicKey, weight: 1}])]);
+
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, guild)
+        ]);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, guild)]);
+
+        const guildBalance = Guild.getDeserialized((await connection.getAccountInfo(guild))?.data as Buffer).reward_balance;
+        expect(guildBalance).toBeGreaterThan(0n);
+
+        // the reward token accounts are not accepted in place of the vesting escrows
+        await expect(sendAndConfirm([claimGuildRewardsIx(keypair.publicKey, guild, stakeDetails, rewardMint, [keypair.publicKey])])).rejects.toThrow();
+
+        await sendAndConfirm([claimGuildRewardsIx(keypair.publicKey, guild, stakeDetails, rewardMint, [keypair.publicKey], true)]);
+
+        const escrow = VestingEscrow.getDeserialized((await connection.getAccountInfo(findVestingEscrow(stakeDetails, keypair.publicKey)))?.data as Buffer);
+
+        expect(escrow.locked_amount).toBe(guildBalance);
+        expect(await getTokenBalance(userRewardAccount)).toBe(0n);
+    })
+
+
+    it("should let anyone crank a claim to the reward account of the holder", async()=>{
+
+        const CRANK_TIP_BPS = 200;
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([configureCrankTipIx(keypair.publicKey, stakeDetails, CRANK_TIP_BPS)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // the cranker pays for its transactions, the holder does not sign
+        const cranker = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(cranker.publicKey, LAMPORTS_PER_SOL), "finalized");
+        const crankerRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, cranker.publicKey);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        // the rewards can not be diverted to the cranker
+        const divertIx = crankClaimRewardsIx(cranker.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint);
+        divertIx.keys[6] = {pubkey: crankerRewardAccount, isSigner: false, isWritable: true};
+        await expect(sendAndConfirm([divertIx], [cranker])).rejects.toThrow();
+
+        // nor can the tip be minted to an account of another mint
+        const otherMint = await createRewardMint(0);
+        const otherTipIx = crankClaimRewardsIx(cranker.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint);
+        otherTipIx.keys[1] = {pubkey: await createAssociatedTokenAccount(connection, keypair, otherMint, cranker.publicKey), isSigner: false, isWritable: true};
+        await expect(sendAndConfirm([otherTipIx], [cranker])).rejects.toThrow();
+
+        await sendAndConfirm([crankClaimRewardsIx(cranker.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint)], [cranker]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+        const netReward = grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n;
+        const tip = netReward * BigInt(CRANK_TIP_BPS) / 10_000n;
+
+        expect(await getTokenBalance(crankerRewardAccount)).toBe(tip);
+        expect(await getTokenBalance(userRewardAccount)).toBe(netReward - tip);
+    })
+
+
+    it("should let the claim delegate of a position claim but not unstake", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        // the hot wallet pays for its transactions, the cold wallet only signs once to register it
+        const hotWallet = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(hotWallet.publicKey, LAMPORTS_PER_SOL), "finalized");
+
+        await expect(sendAndConfirm([
+            delegatedClaimRewardsIx(hotWallet.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint)
+        ], [hotWallet])).rejects.toThrow();
+
+        await sendAndConfirm([setClaimDelegateIx(keypair.publicKey, stakeDetails, nftMintKey, hotWallet.publicKey)]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        expect(stakeBeforeClaim.claim_delegate).toBe(hotWallet.publicKey.toBase58());
+        expect(stakeBeforeClaim.delegated_by).toBe(keypair.publicKey.toBase58());
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        // the rewards can only go to the reward account of the holder
+        const hotWalletRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, hotWallet.publicKey);
+        await expect(sendAndConfirm([
+            delegatedClaimRewardsIx(hotWallet.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint, hotWalletRewardAccount)
+        ], [hotWallet])).rejects.toThrow();
+
+        await sendAndConfirm([
+            delegatedClaimRewardsIx(hotWallet.publicKey, keypair.publicKey, stakeDetails, nftMintKey, rewardMint)
+        ], [hotWallet]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(await getTokenBalance(hotWalletRewardAccount)).toBe(0n);
+
+        // the delegate does not hold the receipt
+        await expect(sendAndConfirm([
+            unstakeIx(hotWallet.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey)
+        ], [hotWallet])).rejects.toThrow();
+    })
+
+
+    it("should claim to the reward destination chosen by the holder", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetadataKey = new PublicKey((await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey})).publicKey.toString());
+        const userRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, keypair.publicKey);
+
+        // the reward account passed to stake must hold the reward mint of the pool
+        const otherMint = await createRewardMint(0);
+        const otherMintAccount = await createAssociatedTokenAccount(connection, keypair, otherMint, keypair.publicKey);
+        await expect(sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, nftMetadataKey, otherMint)
+        ])).rejects.toThrow();
+
+        await sendAndConfirm([stakeIx(keypair.publicKey, stakeDetails, nftMintKey, nftMetadataKey, rewardMint)]);
+
+        // the user migrates to a new wallet
+        const newWallet = Keypair.generate();
+        const newWalletRewardAccount = await createAssociatedTokenAccount(connection, keypair, rewardMint, newWallet.publicKey);
+
+        await expect(sendAndConfirm([
+            setRewardDestinationIx(keypair.publicKey, stakeDetails, nftMintKey, otherMintAccount)
+        ])).rejects.toThrow();
+
+        await sendAndConfirm([setRewardDestinationIx(keypair.publicKey, stakeDetails, nftMintKey, newWalletRewardAccount)]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stakeBeforeClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        expect(stakeBeforeClaim.reward_mint_ata).toBe(newWalletRewardAccount.toBase58());
+        expect(stakeBeforeClaim.reward_destination_holder).toBe(keypair.publicKey.toBase58());
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await expect(sendAndConfirm([
+            claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)
+        ])).rejects.toThrow();
+
+        await sendAndConfirm([
+            claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, newWalletRewardAccount)
+        ]);
+
+        const stakeAfterClaim = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        const grossReward = BigInt(stakeAfterClaim.staked_at - stakeBeforeClaim.staked_at) * REWARD_RATE / REWARD_RATE_SCALE;
+
+        expect(await getTokenBalance(newWalletRewardAccount)).toBe(grossReward - grossReward * BigInt(PROTOCOL_FEE_BPS) / 10_000n);
+        expect(await getTokenBalance(userRewardAccount)).toBe(0n);
+    })
+
+
+    it("should create the reward token account of the user on stake and claim", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+        const userRewardAccount = findAssociatedTokenAddress(keypair.publicKey, rewardMint);
+
+        expect(await connection.getAccountInfo(userRewardAccount)).toBeNull();
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint)
+        ]);
+
+        expect(await getTokenBalance(userRewardAccount)).toBe(0n);
+
+        // the user closes the empty account before claiming
+        await closeAccount(connection, keypair, userRewardAccount, keypair.publicKey, keypair);
+        expect(await connection.getAccountInfo(userRewardAccount)).toBeNull();
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+        await sendAndConfirm([claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+
+        expect(await getTokenBalance(userRewardAccount)).toBeGreaterThan(0n);
+    })
+
+
+    it("should let a sponsor pay the rent of a position and refund it on unstake", async()=>{
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        // the sponsor pays the fees as the first signer and the rent as the payer
+        const sponsor = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(sponsor.publicKey, LAMPORTS_PER_SOL), "finalized");
+
+        const userLamportsBeforeStake = await connection.getBalance(keypair.publicKey);
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, undefined, sponsor.publicKey)
+        ], [sponsor, keypair]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        expect(stake.rent_payer).toBe(sponsor.publicKey.toBase58());
+        expect(await connection.getBalance(keypair.publicKey)).toBe(userLamportsBeforeStake);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await sendAndConfirm([
+            claimRewardsIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, undefined, sponsor.publicKey)
+        ], [sponsor, keypair]);
+
+        expect(await connection.getBalance(keypair.publicKey)).toBe(userLamportsBeforeStake);
+        expect(await getTokenBalance(findAssociatedTokenAddress(keypair.publicKey, rewardMint))).toBeGreaterThan(0n);
+
+        // the rent can only go back to the sponsor
+        await expect(sendAndConfirm([
+            unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)
+        ])).rejects.toThrow();
+
+        const positionRent = (await connection.getBalance(stakeAccount))
+            + (await connection.getBalance(findAssociatedTokenAddress(stakeAccount, nftMintKey)))
+            + (await connection.getBalance(findReceiptAccount(keypair.publicKey, stakeAccount)));
+        const sponsorLamportsBeforeUnstake = await connection.getBalance(sponsor.publicKey);
+
+        await sendAndConfirm([
+            unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, sponsor.publicKey)
+        ]);
+
+        expect(await connection.getBalance(sponsor.publicKey)).toBe(sponsorLamportsBeforeUnstake + positionRent);
+        expect(await connection.getAccountInfo(stakeAccount)).toBeNull();
+
+        // the receipt mint can not be closed, it stays for the next stake of the NFT
+        expect(await connection.getAccountInfo(findReceiptMint(stakeAccount))).not.toBeNull();
+    })
+
+
+    it("should pay the rent of positions out of the rent vault of the pool", async()=>{
+
+        const VAULT_FUNDING = BigInt(LAMPORTS_PER_SOL / 10);
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+        const rentVault = findRentVault(stakeDetails);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+        await sendAndConfirm([
+            configureRentSponsorshipIx(keypair.publicKey, stakeDetails, true),
+            rentVaultIx(34, keypair.publicKey, stakeDetails, VAULT_FUNDING),
+        ]);
+
+        const stakeDetailsData = StakeDetails.getDeserialized((await connection.getAccountInfo(stakeDetails))?.data as Buffer);
+        expect(stakeDetailsData.sponsors_rent).toBe(true);
+        expect(await connection.getBalance(rentVault)).toBe(Number(VAULT_FUNDING));
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, undefined, undefined, rentVault)
+        ]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, keypair.publicKey);
+        const stake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        // the receipt account can be closed by its owner once the receipt is sold, so the vault does not pay it
+        const positionRent = (await connection.getBalance(stakeAccount))
+            + (await connection.getBalance(findAssociatedTokenAddress(stakeAccount, nftMintKey)));
+
+        expect(stake.rent_payer).toBe(rentVault.toBase58());
+        expect(stake.rent_vault_sponsored).toBe(true);
+        expect(await connection.getBalance(rentVault)).toBe(Number(VAULT_FUNDING) - positionRent);
+
+        await sendAndConfirm([
+            unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint, [], keypair.publicKey, rentVault)
+        ]);
+
+        expect(await connection.getBalance(rentVault)).toBe(Number(VAULT_FUNDING));
+
+        // the vault keeps its rent exemption or is emptied
+        await expect(sendAndConfirm([rentVaultIx(35, keypair.publicKey, stakeDetails, VAULT_FUNDING - 1n)])).rejects.toThrow();
+
+        await sendAndConfirm([rentVaultIx(35, keypair.publicKey, stakeDetails, VAULT_FUNDING)]);
+        expect(await connection.getBalance(rentVault)).toBe(0);
+
+        // an empty vault does not hold the stakes back, the payer pays the rent instead
+        await sendAndConfirm([
+            stakeIx(keypair.publicKey, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, undefined, undefined, rentVault)
+        ]);
+
+        const unsponsoredStake = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+        expect(unsponsoredStake.rent_payer).toBe(keypair.publicKey.toBase58());
+        expect(unsponsoredStake.rent_vault_sponsored).toBe(false);
+
+        await sendAndConfirm([unstakeIx(keypair.publicKey, stakeDetails, nftMintKey, rewardMint)]);
+        expect(await connection.getBalance(rentVault)).toBe(0);
+    })
+
+    it("should let a multisig vault stake, claim and unstake through a CPI", async()=>{
+
+        // the stake, its ATA and token CPIs nest one level deeper below the vault program
+        const computeBudgetIx = ComputeBudgetProgram.setComputeUnitLimit({units: 600_000});
+
+        const rewardMint = await createRewardMint(0);
+        const collection = await createNftCollection();
+        const collectionMintKey = new PublicKey(collection.publicKey.toString());
+        const stakeDetails = findStakeDetailsAccount(keypair.publicKey, collectionMintKey);
+
+        await sendAndConfirm([initStakingIx(keypair.publicKey, rewardMint, collectionMintKey, POOL_CONFIG)]);
+
+        // the member of the multisig signs the transactions and pays the rent, the vault pda owns the NFT
+        const authority = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(authority.publicKey, LAMPORTS_PER_SOL), "finalized");
+        const vault = findMockVault(authority.publicKey);
+
+        const nftMint = await mintNft(collection);
+        const nftMintKey = new PublicKey(nftMint.publicKey.toString());
+        const nftMetaData = await fetchMetadataFromSeeds(umi, {mint:nftMint.publicKey});
+
+        const vaultNftAccount = await createAssociatedTokenAccount(connection, keypair, nftMintKey, vault, undefined, undefined, undefined, true);
+        await transfer(connection, keypair, findAssociatedTokenAddress(keypair.publicKey, nftMintKey), vaultNftAccount, keypair, 1);
+        await sendAndConfirm([
+            SystemProgram.transfer({fromPubkey: keypair.publicKey, toPubkey: vault, lamports: LAMPORTS_PER_SOL / 100})
+        ]);
+        const vaultLamportsBeforeStake = await connection.getBalance(vault);
+
+        const stake = stakeIx(vault, stakeDetails, nftMintKey, new PublicKey(nftMetaData.publicKey.toString()), rewardMint, undefined, undefined, authority.publicKey);
+
+        // another wallet cannot make the vault sign
+        const stranger = Keypair.generate();
+        await connection.confirmTransaction(await connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL), "finalized");
+        await expect(sendAndConfirm([computeBudgetIx, mockVaultIx(stranger.publicKey, vault, stake)], [stranger])).rejects.toThrow();
+
+        await sendAndConfirm([computeBudgetIx, mockVaultIx(authority.publicKey, vault, stake)], [authority]);
+
+        const stakeAccount = findStakeAccount(stakeDetails, nftMintKey, vault);
+        const stakeData = Stake.getDeserialized((await connection.getAccountInfo(stakeAccount))?.data as Buffer);
+
+        expect(stakeData.staker).toBe(vault.toBase58());
+        expect(stakeData.rent_payer).toBe(authority.publicKey.toBase58());
+        expect(await getTokenBalance(findAssociatedTokenAddress(stakeAccount, nftMintKey))).toBe(1n);
+        expect(await getTokenBalance(findReceiptAccount(vault, stakeAccount))).toBe(1n);
+        expect(await connection.getBalance(vault)).toBe(vaultLamportsBeforeStake);
+
+        await new Promise(resolve => setTimeout(resolve, 2000));
+
+        await sendAndConfirm([
+            computeBudgetIx,
+            mockVaultIx(authority.publicKey, vault, claimRewardsIx(vault, stakeDetails, nftMintKey, rewardMint, [], vault, undefined, authority.publicKey))
+        ], [authority]);
+
+        const vaultRewardAccount = findAssociatedTokenAddress(vault, rewardMint);
+        expect(await getTokenBalance(vaultRewardAccount)).toBeGreaterThan(0n);
+
+        await sendAndConfirm([
+            computeBudgetIx,
+            mockVaultIx(authority.publicKey, vault, unstakeIx(vault, stakeDetails, nftMintKey, rewardMint, [], vault, authority.publicKey, authority.publicKey))
+        ], [authority]);
+
+        expect(await getTokenBalance(vaultNftAccount)).toBe(1n);
+        expect(await connection.getAccountInfo(stakeAccount)).toBeNull();
+    })
+
+})
+
+function encodeInitStakingData(config: PoolConfig): Buffer {
+    const data = Buffer.alloc(1 + 8 + 8 + 8 + 2 + 8 + 8);
+    let offset = data.writeUInt8(0, 0);
+    offset = data.writeBigUInt64LE(config.rewardRate, offset);
+    offset = data.writeBigInt64LE(config.rewardPeriod, offset);
+    offset = data.writeBigInt64LE(config.minStakeDuration, offset);
+    offset = data.writeUInt16LE(config.earlyUnstakePenaltyBps, offset);
+    offset = data.writeBigUInt64LE(config.earlyUnstakeFlatFee, offset);
+    data.writeBigInt64LE(config.unbondingPeriod, offset);
+    return data;
+}
+
+// 7 is InitProtocolConfig, 8 is UpdateProtocolConfig
+function protocolConfigIx(variant: 7 | 8, authority: PublicKey, protocolConfig: PublicKey, programData?: PublicKey, allowedTokenPrograms: PublicKey[] = [TOKEN_PROGRAM_ID]) {
+    const data = Buffer.alloc(1 + 32 + 32 + 8 + 2 + 8 + 4 + 32 * allowedTokenPrograms.length);
+    let offset = data.writeUInt8(variant, 0);
+    offset += UPGRADE_AUTHORITY.publicKey.toBuffer().copy(data, offset);
+    offset += PROTOCOL_TREASURY.publicKey.toBuffer().copy(data, offset);
+    offset = data.writeBigUInt64LE(POOL_CREATION_FEE, offset);
+    offset = data.writeUInt16LE(PROTOCOL_FEE_BPS, offset);
+    offset = data.writeBigUInt64LE(0n, offset); // no fee cap
+    offset = data.writeUInt32LE(allowedTokenPrograms.length, offset);
+    for(const tokenProgram of allowedTokenPrograms){
+        offset += tokenProgram.toBuffer().copy(data, offset);
+    }
+
+    const keys = [
+        {pubkey: authority, isSigner: true, isWritable: true},
+        {pubkey: protocolConfig, isSigner: false, isWritable: true},
+    ];
+
+    if(programData){
+        keys.push(
+            {pubkey: programData, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        );
+    }
+
+    return new TransactionInstruction({keys, data, programId: PROGRAM_ID});
+}
+
+function initStakingIx(user: PublicKey, rewardMint: PublicKey, collectionMint: PublicKey, config: PoolConfig) {
+    const stakeDetails = findStakeDetailsAccount(user, collectionMint);
+
+    return new TransactionInstruction({
+        keys: [
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: collectionMint, isSigner: false, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: false},
+            {pubkey: findProtocolConfig(), isSigner: false, isWritable: false},
+            {pubkey: PROTOCOL_TREASURY.publicKey, isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: findBadgeMint(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data: encodeInitStakingData(config),
+        programId: PROGRAM_ID,
+    });
+}
+
+// the reward ata of the user is created if missing, the payer sponsors the rent of the position
+// and the rent vault must be passed when the pool sponsors it
+function stakeIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, nftMetadata: PublicKey, rewardMint: PublicKey, referrer?: PublicKey, guild?: PublicKey, payer?: PublicKey, rentVault?: PublicKey, userRewardAccount: PublicKey = findRewardDestination(user, stakeDetails, rewardMint)) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, user);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: nftMetadata, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(user, nftMint), isSigner: false, isWritable: true},
+            {pubkey: userRewardAccount, isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, user), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeAccount, nftMint), isSigner: false, isWritable: true},
+            {pubkey: findReceiptMint(stakeAccount), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, findReceiptMint(stakeAccount)), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: findBadgeMint(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: findBadgeAccount(user, stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: rewardMint, isSigner: false, isWritable: false},
+            ...(guild ? [{pubkey: guild, isSigner: false, isWritable: false}] : []),
+            ...(payer ? [{pubkey: payer, isSigner: true, isWritable: true}] : []),
+            ...(rentVault ? [{pubkey: rentVault, isSigner: false, isWritable: true}] : []),
+            ...(referrer ? [{pubkey: referrer, isSigner: false, isWritable: false}] : []),
+        ],
+        // the rewards of a position staked for a guild are paid to the guild
+        data: Buffer.from(guild || payer ? [1, guild ? 1 : 0, payer ? 1 : 0] : [1]),
+        programId: PROGRAM_ID,
+    });
+}
+
+// the user is the holder of the receipt, who is the staker unless the position was transferred
+function claimRewardsIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, streamAccounts: AccountMeta[] = [], staker: PublicKey = user, rewardDestination: PublicKey = findRewardDestination(user, stakeDetails, rewardMint), payer?: PublicKey) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, staker), isSigner: false, isWritable: true},
+            {pubkey: rewardDestination, isSigner: false, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: findRevenueVault(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            ...(payer ? [{pubkey: payer, isSigner: true, isWritable: true}] : []),
+            ...streamAccounts,
+        ],
+        data: Buffer.from(payer ? [2, 1] : [2]),
+        programId: PROGRAM_ID,
+    });
+}
+
+// the rent of the position goes back to the rent payer, the staker unless it was sponsored, the user passes itself when it paid it
+function unstakeIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, streamAccounts: AccountMeta[] = [], staker: PublicKey = user, rentPayer: PublicKey = staker, payer?: PublicKey) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: true},
+            {pubkey: findReceiptMint(stakeAccount), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, nftMint), isSigner: false, isWritable: true},
+            {pubkey: findRewardDestination(user, stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, staker), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeAccount, nftMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeAccount, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: findBadgeMint(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: findBadgeAccount(staker, stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: findRevenueVault(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: rentPayer, isSigner: false, isWritable: true},
+            ...(payer ? [{pubkey: payer, isSigner: true, isWritable: true}] : []),
+            ...streamAccounts,
+        ],
+        data: Buffer.from(payer ? [3, 1] : [3]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function requestUnstakeIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, streamAccounts: AccountMeta[] = [], staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: findRewardDestination(user, stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, staker), isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: findRevenueVault(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            ...streamAccounts,
+        ],
+        data: Buffer.from([4]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function withdrawNftIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, staker: PublicKey = user, rentPayer: PublicKey = staker) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: true},
+            {pubkey: findReceiptMint(stakeAccount), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, nftMint), isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeAccount, nftMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeAccount, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: findBadgeMint(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: findBadgeAccount(staker, stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: rentPayer, isSigner: false, isWritable: true},
+        ],
+        data: Buffer.from([5]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function cancelUnstakeIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: false},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, staker), isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([6]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function addRewardStreamIx(creator: PublicKey, stakeDetails: PublicKey, streamMint: PublicKey, fundingMode: FundingMode, rewardRate: bigint, rewardPeriod: bigint, startTime = 0n) {
+    const data = Buffer.alloc(1 + 1 + 8 + 8 + 8);
+    let offset = data.writeUInt8(9, 0);
+    offset = data.writeUInt8(fundingMode, offset);
+    offset = data.writeBigUInt64LE(rewardRate, offset);
+    offset = data.writeBigInt64LE(rewardPeriod, offset);
+    data.writeBigInt64LE(startTime, offset);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: streamMint, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, streamMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// the source and the destination of every reward stream, in the order of the pool streams
+function rewardStreamAccounts(user: PublicKey, stakeDetails: StakeDetails): AccountMeta[] {
+    return stakeDetails.reward_streams.flatMap((stream) => {
+        const mint = new PublicKey(stream.mint);
+        const source = stream.funding_mode === FundingMode.Vault ? new PublicKey(stream.vault) : mint;
+
+        return [
+            {pubkey: source, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, mint), isSigner: false, isWritable: true},
+        ];
+    });
+}
+
+type MilestoneConfig = {
+    duration: bigint,
+    name: string,
+    uri: string,
+};
+
+function encodeString(value: string): Buffer {
+    const bytes = Buffer.from(value, "utf-8");
+    const len = Buffer.alloc(4);
+    len.writeUInt32LE(bytes.length);
+    return Buffer.concat([len, bytes]);
+}
+
+function configureMilestonesIx(creator: PublicKey, stakeDetails: PublicKey, milestones: MilestoneConfig[]) {
+    const count = Buffer.alloc(4);
+    count.writeUInt32LE(milestones.length);
+
+    const data = Buffer.concat([
+        Buffer.from([10]),
+        encodeString("Staking Milestones"),
+        encodeString("https://solana.com"),
+        count,
+        ...milestones.map((milestone) => {
+            const duration = Buffer.alloc(8);
+            duration.writeBigInt64LE(milestone.duration);
+            return Buffer.concat([duration, encodeString(milestone.name), encodeString(milestone.uri)]);
+        }),
+    ]);
+
+    const collectionMint = findMilestoneCollection(stakeDetails);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: collectionMint, isSigner: false, isWritable: true},
+            {pubkey: findMetadataAccount(collectionMint), isSigner: false, isWritable: true},
+            {pubkey: findMasterEditionAccount(collectionMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, collectionMint), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function claimMilestoneIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, milestoneMint: PublicKey, milestoneIndex: number, staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+    const collectionMint = findMilestoneCollection(stakeDetails);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: milestoneMint, isSigner: true, isWritable: true},
+            {pubkey: findMetadataAccount(milestoneMint), isSigner: false, isWritable: true},
+            {pubkey: findMasterEditionAccount(milestoneMint), isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(user, milestoneMint), isSigner: false, isWritable: true},
+            {pubkey: collectionMint, isSigner: false, isWritable: false},
+            {pubkey: findMetadataAccount(collectionMint), isSigner: false, isWritable: true},
+            {pubkey: findMasterEditionAccount(collectionMint), isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([11, milestoneIndex]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureLevelsIx(creator: PublicKey, stakeDetails: PublicKey, levels: {duration: bigint, uri: string}[]) {
+    const count = Buffer.alloc(4);
+    count.writeUInt32LE(levels.length);
+
+    const data = Buffer.concat([
+        Buffer.from([12]),
+        count,
+        ...levels.map((level) => {
+            const duration = Buffer.alloc(8);
+            duration.writeBigInt64LE(level.duration);
+            return Buffer.concat([duration, encodeString(level.uri)]);
+        }),
+    ]);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// without a delegate record the stake details account has to be the update authority of the NFT
+function evolveNftIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, delegateRecord: PublicKey = TOKEN_METADATA_PROGRAM_ID, staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: findMetadataAccount(nftMint), isSigner: false, isWritable: true},
+            {pubkey: delegateRecord, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([13]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureSetBonusIx(creator: PublicKey, stakeDetails: PublicKey, tiers: SetBonusTier[]) {
+    const data = Buffer.alloc(1 + 4 + tiers.length * 3);
+    let offset = data.writeUInt8(14, 0);
+    offset = data.writeUInt32LE(tiers.length, offset);
+    for (const tier of tiers) {
+        offset = data.writeUInt8(tier.min_staked, offset);
+        offset = data.writeUInt16LE(tier.bonus_bps, offset);
+    }
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureBoostIx(creator: PublicKey, stakeDetails: PublicKey, tiers: BoostTier[]) {
+    const data = Buffer.alloc(1 + 4 + tiers.length * 10);
+    let offset = data.writeUInt8(15, 0);
+    offset = data.writeUInt32LE(tiers.length, offset);
+    for (const tier of tiers) {
+        offset = data.writeBigUInt64LE(tier.min_locked, offset);
+        offset = data.writeUInt16LE(tier.boost_bps, offset);
+    }
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// 16 is AddBoost, 17 is RemoveBoost
+function boostIx(variant: 16 | 17, user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, amount: bigint, staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    const data = Buffer.alloc(1 + 8);
+    data.writeUInt8(variant, 0);
+    data.writeBigUInt64LE(amount, 1);
+
+    const keys = [
+        {pubkey: user, isSigner: true, isWritable: true},
+        {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+        {pubkey: stakeDetails, isSigner: false, isWritable: false},
+        {pubkey: stakeAccount, isSigner: false, isWritable: true},
+        {pubkey: nftMint, isSigner: false, isWritable: false},
+        {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+        {pubkey: findAssociatedTokenAddress(stakeAccount, rewardMint), isSigner: false, isWritable: true},
+    ];
+
+    if(variant === 16){
+        keys.push(
+            {pubkey: rewardMint, isSigner: false, isWritable: false},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        );
+    } else {
+        keys.push({pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false});
+    }
+
+    return new TransactionInstruction({keys, data, programId: PROGRAM_ID});
+}
+
+function configurePotionIx(creator: PublicKey, stakeDetails: PublicKey, price: bigint, boostBps: number, duration: bigint) {
+    const data = Buffer.alloc(1 + 8 + 2 + 8);
+    let offset = data.writeUInt8(18, 0);
+    offset = data.writeBigUInt64LE(price, offset);
+    offset = data.writeUInt16LE(boostBps, offset);
+    data.writeBigInt64LE(duration, offset);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function buyPotionIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, staker: PublicKey = user) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, staker);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: false},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([19]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function attachRewardMintIx(creator: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey) {
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: false},
+            {pubkey: findProtocolConfig(), isSigner: false, isWritable: false},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([20]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function convertPointsIx(user: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey, amount: bigint) {
+    const data = Buffer.alloc(1 + 8);
+    data.writeUInt8(21, 0);
+    data.writeBigUInt64LE(amount, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: true},
+            {pubkey: findStakerAccount(stakeDetails, user), isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: findFeeTreasury(rewardMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function depositRevenueIx(depositor: PublicKey, stakeDetails: PublicKey, amount: bigint) {
+    const data = Buffer.alloc(1 + 8);
+    data.writeUInt8(22, 0);
+    data.writeBigUInt64LE(amount, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: depositor, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+            {pubkey: findRevenueVault(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureVestingIx(creator: PublicKey, stakeDetails: PublicKey, vestingPeriod: bigint, instantWithdrawPenaltyBps: number) {
+    const data = Buffer.alloc(1 + 8 + 2);
+    data.writeUInt8(23, 0);
+    data.writeBigInt64LE(vestingPeriod, 1);
+    data.writeUInt16LE(instantWithdrawPenaltyBps, 9);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function withdrawVestedIx(user: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey, instant: boolean) {
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: false},
+            {pubkey: findVestingEscrow(stakeDetails, user), isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(user, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([24, instant ? 1 : 0]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureReferralIx(creator: PublicKey, stakeDetails: PublicKey, referralBps: number) {
+    const data = Buffer.alloc(1 + 2);
+    data.writeUInt8(25, 0);
+    data.writeUInt16LE(referralBps, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// appended after the reward stream accounts when the staker of the position was referred, the reward streams are not shared
+function referralAccounts(referrer: PublicKey, rewardMint: PublicKey): AccountMeta[] {
+    return [
+        {pubkey: findAssociatedTokenAddress(referrer, rewardMint), isSigner: false, isWritable: true},
+    ];
+}
+
+function configureGuildIx(authority: PublicKey, stakeDetails: PublicKey, members: {wallet: PublicKey, weight: number}[]) {
+    const data = Buffer.alloc(1 + 4 + members.length * 34);
+    let offset = data.writeUInt8(26, 0);
+    offset = data.writeUInt32LE(members.length, offset);
+    for (const member of members) {
+        offset += member.wallet.toBuffer().copy(data, offset);
+        offset = data.writeUInt16LE(member.weight, offset);
+    }
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: authority, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: findGuildAccount(stakeDetails, authority), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// in a vesting pool the rewards of the members are locked in their vesting escrows
+function claimGuildRewardsIx(payer: PublicKey, guild: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey, members: PublicKey[], vesting = false) {
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: guild, isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: rewardMint, isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+            {pubkey: payer, isSigner: true, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            ...members.map((member) => ({
+                pubkey: vesting ? findVestingEscrow(stakeDetails, member) : findAssociatedTokenAddress(member, rewardMint),
+                isSigner: false,
+                isWritable: true,
+            })),
+        ],
+        data: Buffer.from([27]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureCrankTipIx(creator: PublicKey, stakeDetails: PublicKey, tipBps: number) {
+    const data = Buffer.alloc(1 + 2);
+    data.writeUInt8(28, 0);
+    data.writeUInt16LE(tipBps, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// claims for the holder, who does not sign, and tips the cranker
+function crankClaimRewardsIx(cranker: PublicKey, holder: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, streamAccounts: AccountMeta[] = [], staker: PublicKey = holder) {
+    const claimKeys = claimRewardsIx(holder, stakeDetails, nftMint, rewardMint, streamAccounts, staker).keys;
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: cranker, isSigner: true, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(cranker, rewardMint), isSigner: false, isWritable: true},
+            {...claimKeys[0], isSigner: false},
+            ...claimKeys.slice(1),
+        ],
+        data: Buffer.from([29]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function setClaimDelegateIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, delegate: PublicKey, destination: PublicKey = PublicKey.default) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, user);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: false},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.concat([Buffer.from([30]), delegate.toBuffer(), destination.toBuffer()]),
+        programId: PROGRAM_ID,
+    });
+}
+
+// claims for the holder, who does not sign, to the destination registered with the delegate
+function delegatedClaimRewardsIx(delegate: PublicKey, holder: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardMint: PublicKey, rewardDestination: PublicKey = findAssociatedTokenAddress(holder, rewardMint)) {
+    const claimKeys = claimRewardsIx(holder, stakeDetails, nftMint, rewardMint, [], holder, rewardDestination).keys;
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: delegate, isSigner: true, isWritable: false},
+            {...claimKeys[0], isSigner: false},
+            ...claimKeys.slice(1),
+        ],
+        data: Buffer.from([31]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function setRewardDestinationIx(user: PublicKey, stakeDetails: PublicKey, nftMint: PublicKey, rewardDestination: PublicKey) {
+    const stakeAccount = findStakeAccount(stakeDetails, nftMint, user);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: user, isSigner: true, isWritable: false},
+            {pubkey: findReceiptAccount(user, stakeAccount), isSigner: false, isWritable: false},
+            {pubkey: stakeAccount, isSigner: false, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: nftMint, isSigner: false, isWritable: false},
+            {pubkey: rewardDestination, isSigner: false, isWritable: false},
+        ],
+        data: Buffer.from([32]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function configureRentSponsorshipIx(creator: PublicKey, stakeDetails: PublicKey, enabled: boolean) {
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: true},
+        ],
+        data: Buffer.from([33, enabled ? 1 : 0]),
+        programId: PROGRAM_ID,
+    });
+}
+
+function rentVaultIx(variant: 34 | 35, creator: PublicKey, stakeDetails: PublicKey, amount: bigint) {
+    const data = Buffer.alloc(1 + 8);
+    data.writeUInt8(variant, 0);
+    data.writeBigUInt64LE(amount, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: true},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: findRentVault(stakeDetails), isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+// forwards an instruction through the mock vault program, which signs for the vault of the authority
+function mockVaultIx(authority: PublicKey, vault: PublicKey, ix: TransactionInstruction) {
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: authority, isSigner: true, isWritable: true},
+            {pubkey: ix.programId, isSigner: false, isWritable: false},
+            ...ix.keys.map(key => key.pubkey.equals(vault) ? {...key, isSigner: false} : key),
+        ],
+        data: ix.data,
+        programId: MOCK_VAULT_PROGRAM_ID,
+    });
+}
+
+function findMockVault(authority: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("vault"), authority.toBuffer()], MOCK_VAULT_PROGRAM_ID)[0];
+}
+
+function withdrawTreasuryIx(creator: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey, destination: PublicKey, amount: bigint) {
+    const data = Buffer.alloc(1 + 8);
+    data.writeUInt8(36, 0);
+    data.writeBigUInt64LE(amount, 1);
+
+    return new TransactionInstruction({
+        keys:[
+            {pubkey: creator, isSigner: true, isWritable: false},
+            {pubkey: stakeDetails, isSigner: false, isWritable: false},
+            {pubkey: findAssociatedTokenAddress(stakeDetails, rewardMint), isSigner: false, isWritable: true},
+            {pubkey: destination, isSigner: false, isWritable: true},
+            {pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false},
+        ],
+        data,
+        programId: PROGRAM_ID,
+    });
+}
+
+function findRentVault(stakeDetails: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("rent_vault"), stakeDetails.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findGuildAccount(stakeDetails: PublicKey, authority: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("guild"), stakeDetails.toBuffer(), authority.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findVestingEscrow(stakeDetails: PublicKey, owner: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("vesting"), stakeDetails.toBuffer(), owner.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findRevenueVault(stakeDetails: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("revenue"), stakeDetails.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findStakerAccount(stakeDetails: PublicKey, owner: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("staker"), stakeDetails.toBuffer(), owner.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findMilestoneCollection(stakeDetails: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("milestone_collection"), stakeDetails.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findMetadataAccount(mint: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+        TOKEN_METADATA_PROGRAM_ID,
+    )[0];
+}
+
+function findMasterEditionAccount(mint: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from("edition")],
+        TOKEN_METADATA_PROGRAM_ID,
+    )[0];
+}
+
+function findBadgeMint(stakeDetails: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("badge"), stakeDetails.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findBadgeAccount(owner: PublicKey, stakeDetails: PublicKey) {
+    return findAssociatedTokenAddress(owner, findBadgeMint(stakeDetails), TOKEN_2022_PROGRAM_ID);
+}
+
+function findReceiptMint(stakeAccount: PublicKey) {
+    return PublicKey.findProgramAddressSync([Buffer.from("receipt"), stakeAccount.toBuffer()], PROGRAM_ID)[0];
+}
+
+function findReceiptAccount(holder: PublicKey, stakeAccount: PublicKey) {
+    return findAssociatedTokenAddress(holder, findReceiptMint(stakeAccount));
+}
+
+function findProtocolConfig() {
+    return PublicKey.findProgramAddressSync([Buffer.from("protocol_config")], PROGRAM_ID)[0];
+}
+
+function findProgramDataAccount() {
+    return PublicKey.findProgramAddressSync([PROGRAM_ID.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID)[0];
+}
+
+// points-only pools pass the system program as their reward mint and credit points to the staker account of the user
+function findRewardDestination(user: PublicKey, stakeDetails: PublicKey, rewardMint: PublicKey) {
+    return rewardMint.equals(SystemProgram.programId)
+        ? findStakerAccount(stakeDetails, user)
+        : findAssociatedTokenAddress(user, rewardMint);
+}
+
+function findFeeTreasury(rewardMint: PublicKey) {
+    return findAssociatedTokenAddress(PROTOCOL_TREASURY.publicKey, rewardMint);
+}
+
+function findStakeDetailsAccount(user: PublicKey, collectionMint: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_details"), user.toBuffer(), collectionMint.toBuffer()],
+        PROGRAM_ID,
+    )[0];
+}
 
 function findAssociatedTokenAddress(
     walletAddress: PublicKey,
-    tokenMintAddress: PublicKey
+    tokenMintAddress: PublicKey,
+    tokenProgramId: PublicKey = TOKEN_PROGRAM_ID
 ): PublicKey {
     return PublicKey.findProgramAddressSync(
         [
             walletAddress.toBuffer(),
-            TOKEN_PROGRAM_ID.toBuffer(),
+            tokenProgramId.toBuffer(),
             tokenMintAddress.toBuffer(),
         ],
         ASSOCIATED_TOKEN_PROGRAM_ID
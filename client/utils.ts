@@ -12,19 +12,174 @@ const getPubkeyBase58 = (data:Uint8Array) => {
     return new PublicKey(data).toBase58();
 }
 
+// reward rates are fixed-point numbers with 9 decimal places
+export const REWARD_RATE_SCALE = 1_000_000_000n;
+
+export const MAX_REWARD_STREAMS = 3;
+
+export enum FundingMode {
+    Mint = 0,
+    Vault = 1,
+}
+
+export type RewardStream = {
+    mint: string,
+    funding_mode: FundingMode,
+    vault: string,
+    reward_rate: bigint,
+    reward_period: bigint,
+    start_time: bigint,
+};
+
+export const MAX_MILESTONES = 3;
+
+export type Milestone = {
+    duration: bigint,
+    name: string,
+    uri: string,
+};
+
+const MilestoneSchema = {
+    struct:{
+        duration: 'i64',
+        name: {array: {type: 'u8', len: 32}},
+        uri: {array: {type: 'u8', len: 200}},
+    }
+};
+
+export const MAX_LEVELS = 3;
+
+export type Level = {
+    duration: bigint,
+    uri: string,
+};
+
+const LevelSchema = {
+    struct:{
+        duration: 'i64',
+        uri: {array: {type: 'u8', len: 200}},
+    }
+};
+
+export const MAX_SET_BONUS_TIERS = 4;
+
+export type SetBonusTier = {
+    min_staked: number,
+    bonus_bps: number,
+};
+
+const SetBonusTierSchema = {
+    struct:{
+        min_staked: 'u8',
+        bonus_bps: 'u16',
+    }
+};
+
+export const MAX_BOOST_TIERS = 4;
+
+export type BoostTier = {
+    min_locked: bigint,
+    boost_bps: number,
+};
+
+const BoostTierSchema = {
+    struct:{
+        min_locked: 'u64',
+        boost_bps: 'u16',
+    }
+};
+
+export const MAX_GUILD_MEMBERS = 8;
+
+export type GuildMember = {
+    wallet: string,
+    weight: number,
+};
+
+const GuildMemberSchema = {
+    struct:{
+        wallet: PubkeySchema,
+        weight: 'u16',
+    }
+};
+
+// fixed-size strings are padded with zeros
+const getUnpaddedString = (data:Uint8Array) => {
+    return Buffer.from(data).toString("utf-8").replace(/\0+$/, "");
+}
+
+const RewardStreamSchema = {
+    struct:{
+        mint: PubkeySchema,
+        funding_mode: 'u8',
+        vault: PubkeySchema,
+        reward_rate: 'u64',
+        reward_period: 'i64',
+        start_time: 'i64',
+    }
+};
+
 export class Stake{
     stake_details_key : string;
     nft_mint : string;
     reward_mint: string;
     reward_mint_ata: string;
     staked_at: number;
+    reward_carry: bigint;
+    initial_staked_at: number;
+    unbonding_ends_at: number;
+    stream_carries: bigint[];
+    staker: string;
+    receipt_mint: string;
+    bump_seed: number;
+    milestones_claimed: number;
+    level: number;
+    boost_index_snapshot: bigint;
+    locked_boost: bigint;
+    potion_boost_bps: number;
+    potion_expires_at: bigint;
+    revenue_index_snapshot: bigint;
+    guild: string;
+    claim_delegate: string;
+    claim_destination: string;
+    delegated_by: string;
+    reward_destination_holder: string;
+    rent_payer: string;
+    left_set: boolean;
+    boost_bps: number;
+    revenue_carry: bigint;
+    rent_vault_sponsored: boolean;
 
-    constructor(stake_details_key:string, nft_mint:string, reward_mint:string, reward_mint_ata:string, staked_at:number){
+    constructor(stake_details_key:string, nft_mint:string, reward_mint:string, reward_mint_ata:string, staked_at:number, reward_carry:bigint, initial_staked_at:number, unbonding_ends_at:number, stream_carries:bigint[], staker:string, receipt_mint:string, bump_seed:number, milestones_claimed:number, level:number, boost_index_snapshot:bigint, locked_boost:bigint, potion_boost_bps:number, potion_expires_at:bigint, revenue_index_snapshot:bigint, guild:string, claim_delegate:string, claim_destination:string, delegated_by:string, reward_destination_holder:string, rent_payer:string, left_set:boolean, boost_bps:number, revenue_carry:bigint, rent_vault_sponsored:boolean){
         this.stake_details_key = stake_details_key;
         this.nft_mint = nft_mint;
         this.reward_mint = reward_mint;
         this.reward_mint_ata = reward_mint_ata;
         this.staked_at = staked_at;
+        this.reward_carry = reward_carry;
+        this.initial_staked_at = initial_staked_at;
+        this.unbonding_ends_at = unbonding_ends_at;
+        this.stream_carries = stream_carries;
+        this.staker = staker;
+        this.receipt_mint = receipt_mint;
+        this.bump_seed = bump_seed;
+        this.milestones_claimed = milestones_claimed;
+        this.level = level;
+        this.boost_index_snapshot = boost_index_snapshot;
+        this.locked_boost = locked_boost;
+        this.potion_boost_bps = potion_boost_bps;
+        this.potion_expires_at = potion_expires_at;
+        this.revenue_index_snapshot = revenue_index_snapshot;
+        this.guild = guild;
+        this.claim_delegate = claim_delegate;
+        this.claim_destination = claim_destination;
+        this.delegated_by = delegated_by;
+        this.reward_destination_holder = reward_destination_holder;
+        this.rent_payer = rent_payer;
+        this.left_set = left_set;
+        this.boost_bps = boost_bps;
+        this.revenue_carry = revenue_carry;
+        this.rent_vault_sponsored = rent_vault_sponsored;
     }
 
     static getDeserialized(data:Buffer){
@@ -35,7 +190,32 @@ export class Stake{
                 nft_mint: PubkeySchema,
                 reward_mint: PubkeySchema, 
                 reward_mint_ata:PubkeySchema,
-                staked_at: 'i64'
+                staked_at: 'i64',
+                reward_carry: 'u128',
+                initial_staked_at: 'i64',
+                unbonding_ends_at: 'i64',
+                stream_carries: {array: {type: 'u128', len: MAX_REWARD_STREAMS}},
+                staker: PubkeySchema,
+                receipt_mint: PubkeySchema,
+                bump_seed: 'u8',
+                milestones_claimed: 'u8',
+                level: 'u8',
+                boost_index_snapshot: 'u128',
+                locked_boost: 'u64',
+                potion_boost_bps: 'u16',
+                potion_expires_at: 'i64',
+                revenue_index_snapshot: 'u128',
+                guild: PubkeySchema,
+                claim_delegate: PubkeySchema,
+                claim_destination: PubkeySchema,
+                delegated_by: PubkeySchema,
+                reward_destination_holder: PubkeySchema,
+                rent_payer: PubkeySchema,
+                left_set: 'bool',
+                boost_bps: 'u16',
+                revenue_carry: 'u64',
+                rent_vault_sponsored: 'bool',
+                reserved: {array: {type: 'u8', len: 116}},
             }
         },data);
 
@@ -50,7 +230,55 @@ export class Stake{
             // @ts-ignore
             getPubkeyBase58(deserialized.reward_mint_ata),
             // @ts-ignore
-            deserialized.staked_at
+            deserialized.staked_at,
+            // @ts-ignore
+            BigInt(deserialized.reward_carry),
+            // @ts-ignore
+            deserialized.initial_staked_at,
+            // @ts-ignore
+            deserialized.unbonding_ends_at,
+            // @ts-ignore
+            deserialized.stream_carries.map(BigInt),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.staker),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.receipt_mint),
+            // @ts-ignore
+            deserialized.bump_seed,
+            // @ts-ignore
+            deserialized.milestones_claimed,
+            // @ts-ignore
+            deserialized.level,
+            // @ts-ignore
+            BigInt(deserialized.boost_index_snapshot),
+            // @ts-ignore
+            BigInt(deserialized.locked_boost),
+            // @ts-ignore
+            deserialized.potion_boost_bps,
+            // @ts-ignore
+            BigInt(deserialized.potion_expires_at),
+            // @ts-ignore
+            BigInt(deserialized.revenue_index_snapshot),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.guild),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.claim_delegate),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.claim_destination),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.delegated_by),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.reward_destination_holder),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.rent_payer),
+            // @ts-ignore
+            deserialized.left_set,
+            // @ts-ignore
+            deserialized.boost_bps,
+            // @ts-ignore
+            BigInt(deserialized.revenue_carry),
+            // @ts-ignore
+            deserialized.rent_vault_sponsored
         )
 
         return stake;
@@ -62,12 +290,119 @@ export class StakeDetails{
     reward_token_mint: string;
     collection_mint: string;
     bump_seed: number;
+    reward_rate: bigint;
+    reward_period: bigint;
+    treasury: string;
+    min_stake_duration: bigint;
+    early_unstake_penalty_bps: number;
+    early_unstake_flat_fee: bigint;
+    unbonding_period: bigint;
+    fee_treasury: string;
+    protocol_fee_bps: number;
+    protocol_fee_cap: bigint;
+    reward_stream_count: number;
+    reward_streams: RewardStream[];
+    badge_mint: string;
+    milestone_collection: string;
+    milestone_count: number;
+    milestones: Milestone[];
+    level_count: number;
+    levels: Level[];
+    set_bonus_tier_count: number;
+    set_bonus_tiers: SetBonusTier[];
+    boost_tier_count: number;
+    boost_tiers: BoostTier[];
+    potion_price: bigint;
+    potion_boost_bps: number;
+    potion_duration: bigint;
+    revenue_vault: string;
+    revenue_per_share: bigint;
+    total_staked: bigint;
+    vesting_period: bigint;
+    instant_withdraw_penalty_bps: number;
+    referral_bps: number;
+    crank_tip_bps: number;
+    sponsors_rent: boolean;
+    reward_token_program: string;
 
-    constructor(creator:string, reward_token_mint:string, collection_mint:string, bump_seed:number){
+    constructor(
+        creator:string, 
+        reward_token_mint:string, 
+        collection_mint:string, 
+        bump_seed:number, 
+        reward_rate:bigint, 
+        reward_period:bigint,
+        treasury:string,
+        min_stake_duration:bigint,
+        early_unstake_penalty_bps:number,
+        early_unstake_flat_fee:bigint,
+        unbonding_period:bigint,
+        fee_treasury:string,
+        protocol_fee_bps:number,
+        protocol_fee_cap:bigint,
+        reward_stream_count:number,
+        reward_streams:RewardStream[],
+        badge_mint:string,
+        milestone_collection:string,
+        milestone_count:number,
+        milestones:Milestone[],
+        level_count:number,
+        levels:Level[],
+        set_bonus_tier_count:number,
+        set_bonus_tiers:SetBonusTier[],
+        boost_tier_count:number,
+        boost_tiers:BoostTier[],
+        potion_price:bigint,
+        potion_boost_bps:number,
+        potion_duration:bigint,
+        revenue_vault:string,
+        revenue_per_share:bigint,
+        total_staked:bigint,
+        vesting_period:bigint,
+        instant_withdraw_penalty_bps:number,
+        referral_bps:number,
+        crank_tip_bps:number,
+        sponsors_rent:boolean,
+        reward_token_program:string,
+    ){
         this.bump_seed = bump_seed;
         this.creator = creator;
         this.reward_token_mint = reward_token_mint;
         this.collection_mint = collection_mint;
+        this.reward_rate = reward_rate;
+        this.reward_period = reward_period;
+        this.treasury = treasury;
+        this.min_stake_duration = min_stake_duration;
+        this.early_unstake_penalty_bps = early_unstake_penalty_bps;
+        this.early_unstake_flat_fee = early_unstake_flat_fee;
+        this.unbonding_period = unbonding_period;
+        this.fee_treasury = fee_treasury;
+        this.protocol_fee_bps = protocol_fee_bps;
+        this.protocol_fee_cap = protocol_fee_cap;
+        this.reward_stream_count = reward_stream_count;
+        this.reward_streams = reward_streams;
+        this.badge_mint = badge_mint;
+        this.milestone_collection = milestone_collection;
+        this.milestone_count = milestone_count;
+        this.milestones = milestones;
+        this.level_count = level_count;
+        this.levels = levels;
+        this.set_bonus_tier_count = set_bonus_tier_count;
+        this.set_bonus_tiers = set_bonus_tiers;
+        this.boost_tier_count = boost_tier_count;
+        this.boost_tiers = boost_tiers;
+        this.potion_price = potion_price;
+        this.potion_boost_bps = potion_boost_bps;
+        this.potion_duration = potion_duration;
+        this.revenue_vault = revenue_vault;
+        this.revenue_per_share = revenue_per_share;
+        this.total_staked = total_staked;
+        this.vesting_period = vesting_period;
+        this.instant_withdraw_penalty_bps = instant_withdraw_penalty_bps;
+        this.referral_bps = referral_bps;
+        this.crank_tip_bps = crank_tip_bps;
+        this.sponsors_rent = sponsors_rent;
+        this.reward_token_program = reward_token_program;
     }
 
     static getDeserialized(data:Buffer){
@@ -77,6 +412,41 @@ export class StakeDetails{
                 reward_token_mint: PubkeySchema,
                 collection_mint: PubkeySchema,
                 bump_seed: 'u8',
+                reward_rate: 'u64',
+                reward_period: 'i64',
+                treasury: PubkeySchema,
+                min_stake_duration: 'i64',
+                early_unstake_penalty_bps: 'u16',
+                early_unstake_flat_fee: 'u64',
+                unbonding_period: 'i64',
+                fee_treasury: PubkeySchema,
+                protocol_fee_bps: 'u16',
+                protocol_fee_cap: 'u64',
+                reward_stream_count: 'u8',
+                reward_streams: {array: {type: RewardStreamSchema, len: MAX_REWARD_STREAMS}},
+                badge_mint: PubkeySchema,
+                milestone_collection: PubkeySchema,
+                milestone_count: 'u8',
+                milestones: {array: {type: MilestoneSchema, len: MAX_MILESTONES}},
+                level_count: 'u8',
+                levels: {array: {type: LevelSchema, len: MAX_LEVELS}},
+                set_bonus_tier_count: 'u8',
+                set_bonus_tiers: {array: {type: SetBonusTierSchema, len: MAX_SET_BONUS_TIERS}},
+                boost_tier_count: 'u8',
+                boost_tiers: {array: {type: BoostTierSchema, len: MAX_BOOST_TIERS}},
+                potion_price: 'u64',
+                potion_boost_bps: 'u16',
+                potion_duration: 'i64',
+                revenue_vault: PubkeySchema,
+                revenue_per_share: 'u128',
+                total_staked: 'u64',
+                vesting_period: 'i64',
+                instant_withdraw_penalty_bps: 'u16',
+                referral_bps: 'u16',
+                crank_tip_bps: 'u16',
+                sponsors_rent: 'bool',
+                reward_token_program: PubkeySchema,
+                reserved: {array: {type: 'u8', len: 96}},
             }
         },data);
 
@@ -88,9 +458,309 @@ export class StakeDetails{
             // @ts-ignore
             getPubkeyBase58(deserialized.collection_mint),
             // @ts-ignore
-            deserialized.bump_seed
+            deserialized.bump_seed,
+            // @ts-ignore
+            BigInt(deserialized.reward_rate),
+            // @ts-ignore
+            BigInt(deserialized.reward_period),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.treasury),
+            // @ts-ignore
+            BigInt(deserialized.min_stake_duration),
+            // @ts-ignore
+            deserialized.early_unstake_penalty_bps,
+            // @ts-ignore
+            BigInt(deserialized.early_unstake_flat_fee),
+            // @ts-ignore
+            BigInt(deserialized.unbonding_period),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.fee_treasury),
+            // @ts-ignore
+            deserialized.protocol_fee_bps,
+            // @ts-ignore
+            BigInt(deserialized.protocol_fee_cap),
+            // @ts-ignore
+            deserialized.reward_stream_count,
+            // @ts-ignore
+            deserialized.reward_streams.slice(0, deserialized.reward_stream_count).map((stream) => ({
+                mint: getPubkeyBase58(stream.mint),
+                funding_mode: stream.funding_mode,
+                vault: getPubkeyBase58(stream.vault),
+                reward_rate: BigInt(stream.reward_rate),
+                reward_period: BigInt(stream.reward_period),
+                start_time: BigInt(stream.start_time),
+            })),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.badge_mint),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.milestone_collection),
+            // @ts-ignore
+            deserialized.milestone_count,
+            // @ts-ignore
+            deserialized.milestones.slice(0, deserialized.milestone_count).map((milestone) => ({
+                duration: BigInt(milestone.duration),
+                name: getUnpaddedString(milestone.name),
+                uri: getUnpaddedString(milestone.uri),
+            })),
+            // @ts-ignore
+            deserialized.level_count,
+            // @ts-ignore
+            deserialized.levels.slice(0, deserialized.level_count).map((level) => ({
+                duration: BigInt(level.duration),
+                uri: getUnpaddedString(level.uri),
+            })),
+            // @ts-ignore
+            deserialized.set_bonus_tier_count,
+            // @ts-ignore
+            deserialized.set_bonus_tiers.slice(0, deserialized.set_bonus_tier_count),
+            // @ts-ignore
+            deserialized.boost_tier_count,
+            // @ts-ignore
+            deserialized.boost_tiers.slice(0, deserialized.boost_tier_count).map((tier) => ({
+                min_locked: BigInt(tier.min_locked),
+                boost_bps: tier.boost_bps,
+            })),
+            // @ts-ignore
+            BigInt(deserialized.potion_price),
+            // @ts-ignore
+            deserialized.potion_boost_bps,
+            // @ts-ignore
+            BigInt(deserialized.potion_duration),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.revenue_vault),
+            // @ts-ignore
+            BigInt(deserialized.revenue_per_share),
+            // @ts-ignore
+            BigInt(deserialized.total_staked),
+            // @ts-ignore
+            BigInt(deserialized.vesting_period),
+            // @ts-ignore
+            deserialized.instant_withdraw_penalty_bps,
+            // @ts-ignore
+            deserialized.referral_bps,
+            // @ts-ignore
+            deserialized.crank_tip_bps,
+            // @ts-ignore
+            deserialized.sponsors_rent,
+            // @ts-ignore
+            getPubkeyBase58(deserialized.reward_token_program)
         )
 
         return stakeDetails;
     }
-}
\ No newline at end of file
+}
+
+export class ProtocolConfig{
+    admin: string;
+    treasury: string;
+    creation_fee: bigint;
+    default_fee_bps: number;
+    default_fee_cap: bigint;
+    allowed_token_programs: string[];
+    bump_seed: number;
+
+    constructor(admin:string, treasury:string, creation_fee:bigint, default_fee_bps:number, default_fee_cap:bigint, allowed_token_programs:string[], bump_seed:number){
+        this.admin = admin;
+        this.treasury = treasury;
+        this.creation_fee = creation_fee;
+        this.default_fee_bps = default_fee_bps;
+        this.default_fee_cap = default_fee_cap;
+        this.allowed_token_programs = allowed_token_programs;
+        this.bump_seed = bump_seed;
+    }
+
+    static getDeserialized(data:Buffer){
+        const deserialized = borsh.deserialize({
+            struct:{
+                admin: PubkeySchema,
+                treasury: PubkeySchema,
+                creation_fee: 'u64',
+                default_fee_bps: 'u16',
+                default_fee_cap: 'u64',
+                allowed_token_programs: {array: {type: PubkeySchema, len: 2}},
+                bump_seed: 'u8',
+            }
+        },data);
+
+        return new ProtocolConfig(
+            // @ts-ignore
+            getPubkeyBase58(deserialized.admin),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.treasury),
+            // @ts-ignore
+            BigInt(deserialized.creation_fee),
+            // @ts-ignore
+            deserialized.default_fee_bps,
+            // @ts-ignore
+            BigInt(deserialized.default_fee_cap),
+            // @ts-ignore
+            deserialized.allowed_token_programs.map(getPubkeyBase58),
+            // @ts-ignore
+            deserialized.bump_seed
+        );
+    }
+}
+
+export class Staker{
+    stake_details_key: string;
+    owner: string;
+    staked_count: number;
+    boost_index: bigint;
+    last_update: bigint;
+    bump_seed: number;
+    points: bigint;
+    referrer: string;
+    set_bonus_bps: number;
+
+    constructor(stake_details_key:string, owner:string, staked_count:number, boost_index:bigint, last_update:bigint, bump_seed:number, points:bigint, referrer:string, set_bonus_bps:number){
+        this.stake_details_key = stake_details_key;
+        this.owner = owner;
+        this.staked_count = staked_count;
+        this.boost_index = boost_index;
+        this.last_update = last_update;
+        this.bump_seed = bump_seed;
+        this.points = points;
+        this.referrer = referrer;
+        this.set_bonus_bps = set_bonus_bps;
+    }
+
+    static getDeserialized(data:Buffer){
+        const deserialized = borsh.deserialize({
+            struct:{
+                stake_details_key: PubkeySchema,
+                owner: PubkeySchema,
+                staked_count: 'u8',
+                boost_index: 'u128',
+                last_update: 'i64',
+                bump_seed: 'u8',
+                points: 'u64',
+                referrer: PubkeySchema,
+                set_bonus_bps: 'u16',
+                reserved: {array: {type: 'u8', len: 62}},
+            }
+        },data);
+
+        return new Staker(
+            // @ts-ignore
+            getPubkeyBase58(deserialized.stake_details_key),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.owner),
+            // @ts-ignore
+            deserialized.staked_count,
+            // @ts-ignore
+            BigInt(deserialized.boost_index),
+            // @ts-ignore
+            BigInt(deserialized.last_update),
+            // @ts-ignore
+            deserialized.bump_seed,
+            // @ts-ignore
+            BigInt(deserialized.points),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.referrer),
+            // @ts-ignore
+            deserialized.set_bonus_bps
+        );
+    }
+}
+
+export class VestingEscrow{
+    stake_details_key: string;
+    owner: string;
+    locked_amount: bigint;
+    vested_amount: bigint;
+    last_update: bigint;
+    vesting_ends_at: bigint;
+    bump_seed: number;
+
+    constructor(stake_details_key:string, owner:string, locked_amount:bigint, vested_amount:bigint, last_update:bigint, vesting_ends_at:bigint, bump_seed:number){
+        this.stake_details_key = stake_details_key;
+        this.owner = owner;
+        this.locked_amount = locked_amount;
+        this.vested_amount = vested_amount;
+        this.last_update = last_update;
+        this.vesting_ends_at = vesting_ends_at;
+        this.bump_seed = bump_seed;
+    }
+
+    static getDeserialized(data:Buffer){
+        const deserialized = borsh.deserialize({
+            struct:{
+                stake_details_key: PubkeySchema,
+                owner: PubkeySchema,
+                locked_amount: 'u64',
+                vested_amount: 'u64',
+                last_update: 'i64',
+                vesting_ends_at: 'i64',
+                bump_seed: 'u8',
+                reserved: {array: {type: 'u8', len: 64}},
+            }
+        },data);
+
+        return new VestingEscrow(
+            // @ts-ignore
+            getPubkeyBase58(deserialized.stake_details_key),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.owner),
+            // @ts-ignore
+            BigInt(deserialized.locked_amount),
+            // @ts-ignore
+            BigInt(deserialized.vested_amount),
+            // @ts-ignore
+            BigInt(deserialized.last_update),
+            // @ts-ignore
+            BigInt(deserialized.vesting_ends_at),
+            // @ts-ignore
+            deserialized.bump_seed
+        );
+    }
+}
+
+export class Guild{
+    stake_details_key: string;
+    authority: string;
+    member_count: number;
+    members: GuildMember[];
+    reward_balance: bigint;
+    bump_seed: number;
+
+    constructor(stake_details_key:string, authority:string, member_count:number, members:GuildMember[], reward_balance:bigint, bump_seed:number){
+        this.stake_details_key = stake_details_key;
+        this.authority = authority;
+        this.member_count = member_count;
+        this.members = members;
+        this.reward_balance = reward_balance;
+        this.bump_seed = bump_seed;
+    }
+
+    static getDeserialized(data:Buffer){
+        const deserialized = borsh.deserialize({
+            struct:{
+                stake_details_key: PubkeySchema,
+                authority: PubkeySchema,
+                member_count: 'u8',
+                members: {array: {type: GuildMemberSchema, len: MAX_GUILD_MEMBERS}},
+                reward_balance: 'u64',
+                bump_seed: 'u8',
+                reserved: {array: {type: 'u8', len: 64}},
+            }
+        },data);
+
+        return new Guild(
+            // @ts-ignore
+            getPubkeyBase58(deserialized.stake_details_key),
+            // @ts-ignore
+            getPubkeyBase58(deserialized.authority),
+            // @ts-ignore
+            deserialized.member_count,
+            // @ts-ignore
+            deserialized.members.slice(0, deserialized.member_count).map((member) => ({
+                wallet: getPubkeyBase58(member.wallet),
+                weight: member.weight,
+            })),
+            // @ts-ignore
+            BigInt(deserialized.reward_balance),
+            // @ts-ignore
+            deserialized.bump_seed
+        );
+    }
+}
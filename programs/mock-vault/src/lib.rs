@@ -0,0 +1,47 @@
+//! Minimal stand-in for a multisig vault, e.g. a Squads vault, used by the client tests. The vault of an
+//! authority is the pda `["vault", authority]`, it holds assets and signs the instructions forwarded by its
+//! authority through a CPI.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint, entrypoint::ProgramResult, instruction::{AccountMeta, Instruction}, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey
+};
+
+entrypoint!(process_instruction);
+
+/// Forwards the instruction data to the target program. The accounts are the authority, the target program
+/// and the accounts of the forwarded instruction, in which the vault of the authority is marked as a signer.
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let authority = next_account_info(iter)?;
+
+    if !authority.is_signer {
+        msg!("Authority is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let target_program = next_account_info(iter)?;
+
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.key.as_ref()], program_id);
+
+    let forwarded_accounts = iter.as_slice();
+
+    let metas = forwarded_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer || *account.key == vault,
+        is_writable: account.is_writable,
+    }).collect();
+
+    let instruction = Instruction {
+        program_id: *target_program.key,
+        accounts: metas,
+        data: data.to_vec(),
+    };
+
+    invoke_signed(
+        &instruction,
+        forwarded_accounts,
+        &[&[b"vault", authority.key.as_ref(), &[bump]]],
+    )
+}
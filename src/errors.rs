@@ -26,6 +26,126 @@ pub enum NftStakingError{
 
     #[error("Lamports overflow occurred while closing Stake Account")]
     StakeAccountCloseError,
+
+    #[error("Reward rate and reward period must be greater than 0")]
+    InvalidRewardRate,
+
+    #[error("Reward amount overflowed")]
+    RewardOverflow,
+
+    #[error("Basis points must not exceed 10000")]
+    InvalidBasisPoints,
+
+    #[error("The pool has an unbonding period, request the unstake first")]
+    UnbondingRequired,
+
+    #[error("The pool does not have an unbonding period")]
+    UnbondingDisabled,
+
+    #[error("The stake is unbonding")]
+    StakeUnbonding,
+
+    #[error("No unstake was requested for the stake")]
+    UnstakeNotRequested,
+
+    #[error("The unbonding period has not finished yet")]
+    UnbondingNotFinished,
+
+    #[error("The protocol fee exceeds the allowed maximum")]
+    ProtocolFeeTooHigh,
+
+    #[error("The token account does not hold the pool reward token")]
+    RewardMintMismatch,
+
+    #[error("The signer is not allowed to perform this action")]
+    Unauthorized,
+
+    #[error("The token program is not allowed by the protocol")]
+    TokenProgramNotAllowed,
+
+    #[error("The pool already has the maximum number of reward streams")]
+    TooManyRewardStreams,
+
+    #[error("The pool already pays rewards in this token")]
+    DuplicateRewardStream,
+
+    #[error("The signer does not hold the receipt of the stake")]
+    ReceiptNotHeld,
+
+    #[error("The milestones of the pool are already configured")]
+    MilestonesAlreadyConfigured,
+
+    #[error("Invalid milestone")]
+    InvalidMilestone,
+
+    #[error("The milestone was already claimed for the stake")]
+    MilestoneAlreadyClaimed,
+
+    #[error("The stake has not reached the milestone yet")]
+    MilestoneNotReached,
+
+    #[error("Invalid level")]
+    InvalidLevel,
+
+    #[error("The NFT has not been staked long enough for the next level")]
+    LevelNotReached,
+
+    #[error("Invalid set bonus tier")]
+    InvalidSetBonus,
+
+    #[error("Invalid boost tier")]
+    InvalidBoost,
+
+    #[error("Not enough reward tokens are locked in the boost vault")]
+    InsufficientBoost,
+
+    #[error("The pool does not sell potions")]
+    PotionUnavailable,
+
+    #[error("A potion is still active on the stake")]
+    PotionActive,
+
+    #[error("The pool does not have a reward mint yet")]
+    PointsOnlyPool,
+
+    #[error("Not enough points")]
+    InsufficientPoints,
+
+    #[error("No NFT is staked in the pool to share the revenue with")]
+    NoStakedNfts,
+
+    #[error("Not enough lamports in the revenue vault")]
+    InsufficientRevenue,
+
+    #[error("Invalid vesting configuration")]
+    InvalidVesting,
+
+    #[error("No vested rewards to withdraw")]
+    NothingVested,
+
+    #[error("Invalid referral")]
+    InvalidReferral,
+
+    #[error("Invalid guild")]
+    InvalidGuild,
+
+    #[error("Claims not signed by the holder only pay rewards to reward token accounts")]
+    CrankUnsupported,
+
+    #[error("Invalid crank tip")]
+    InvalidCrankTip,
+
+    #[error("Signer is not the claim delegate of the position")]
+    InvalidClaimDelegate,
+
+    #[error("Not enough lamports in the rent vault")]
+    InsufficientRentVault,
+
+    #[error("A reward stream vault can not pay what a leaving position is owed")]
+    RewardStreamVaultShort,
+
+    #[error("The guild rewards must be distributed before the members change")]
+    GuildRewardsNotDistributed,
 }
 
 impl From<NftStakingError> for ProgramError{
@@ -0,0 +1,140 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+use spl_associated_token_account::{get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent};
+use spl_token::instruction::transfer;
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::load_stake_for_holder};
+
+#[derive(BorshDeserialize)]
+pub struct BoostArgs {
+    pub amount: u64, // reward tokens to lock or unlock
+}
+
+/// Locks reward tokens in the boost vault of a position to raise its reward multiplier.
+/// The rewards accrued so far keep the previous multiplier.
+pub fn add_boost(program_id: &Pubkey, accounts: &[AccountInfo], args: BoostArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer || !user_account.is_writable {
+        msg!("User Account is Not Signer or Writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let user_reward_token_account = next_account_info(iter)?; // source of the locked tokens
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let boost_vault_account = next_account_info(iter)?; // reward token ata of the stake account
+    if !boost_vault_account.is_writable {
+        msg!("Boost Vault Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_mint_account = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let associated_token_program = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    if stake_data.is_unbonding() {
+        msg!("Stake is unbonding until {}, it can not be boosted", stake_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
+
+    if args.amount == 0 {
+        msg!("Boost amount must be positive");
+        return Err(NftStakingError::InvalidBoost.into());
+    }
+
+    if stake_details_data.is_points_only() {
+        msg!("The pool has no reward token to lock yet");
+        return Err(NftStakingError::PointsOnlyPool.into());
+    }
+
+    // stakes made while the pool was points-only learn its reward mint here
+    stake_data.reward_mint = stake_details_data.reward_token_mint;
+
+    if stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let boost_vault_key = get_associated_token_address_with_program_id(
+        stake_account.key,
+        reward_mint_account.key,
+        token_program.key
+    );
+
+    if boost_vault_key != *boost_vault_account.key {
+        msg!("Boost Vault expected : {}, received : {}", boost_vault_key, boost_vault_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(user_account.key, stake_account.key, reward_mint_account.key, token_program.key),
+        &[
+            user_account.clone(),
+            boost_vault_account.clone(),
+            stake_account.clone(),
+            reward_mint_account.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ]
+    )?;
+
+    stake_data.checkpoint_rewards(&stake_details_data, Clock::get()?.unix_timestamp)?;
+
+    invoke(
+        &transfer(token_program.key, user_reward_token_account.key, boost_vault_account.key, user_account.key, &[user_account.key], args.amount)?,
+        &[user_reward_token_account.clone(), boost_vault_account.clone(), user_account.clone(), token_program.clone()]
+    )?;
+
+    let locked_boost = stake_data.locked_boost.checked_add(args.amount).ok_or(NftStakingError::RewardOverflow)?;
+    stake_data.set_locked_boost(&stake_details_data, locked_boost);
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("{} reward tokens locked, boost : {} bps", stake_data.locked_boost, stake_data.boost_bps);
+
+    Ok(())
+}
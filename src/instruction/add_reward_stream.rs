@@ -0,0 +1,155 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar
+};
+use spl_associated_token_account::{get_associated_token_address_with_program_id, instruction::create_associated_token_account};
+use spl_token::state::Mint;
+
+use crate::{errors::NftStakingError, state::{FundingMode, RewardStream, StakeDetails, MAX_REWARD_STREAMS}};
+
+#[derive(BorshDeserialize)]
+pub struct AddRewardStreamArgs {
+    pub funding_mode: FundingMode,
+    pub reward_rate: u64,   // fixed-point, see REWARD_RATE_SCALE
+    pub reward_period: i64, // in seconds
+    pub start_time: i64,    // a start time in the past starts the stream now
+}
+
+/// Adds an extra reward token to the pool. In Mint mode the stake details pda must already be
+/// the mint authority, in Vault mode the ata of the stake details pda is created as the vault
+/// and anyone can fund it with a plain token transfer.
+pub fn add_reward_stream(program_id: &Pubkey, accounts: &[AccountInfo], args: AddRewardStreamArgs) -> ProgramResult {
+
+    if args.reward_rate == 0 || args.reward_period <= 0 {
+        msg!("Invalid reward rate : {} per {} seconds", args.reward_rate, args.reward_period);
+        return Err(NftStakingError::InvalidRewardRate.into());
+    }
+
+    let iter = &mut accounts.iter();
+
+    // isSigner and isWritable, pays for the vault in Vault mode
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer || !creator.is_writable {
+        msg!("Creator account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stream_mint = next_account_info(iter)?;
+
+    // isWritable, ata of the stake details account for the stream mint, only used in Vault mode
+    let vault_account = next_account_info(iter)?;
+
+    let token_program = next_account_info(iter)?;
+
+    let associated_token_program = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can add reward streams", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    let stream_count = stake_details.reward_stream_count as usize;
+
+    if stream_count >= MAX_REWARD_STREAMS {
+        msg!("The pool already has the maximum of {} reward streams", MAX_REWARD_STREAMS);
+        return Err(NftStakingError::TooManyRewardStreams.into());
+    }
+
+    if stream_mint.owner != token_program.key || *token_program.key != stake_details.token_program_id() {
+        msg!("Stream mint {} is not owned by the token program", stream_mint.key);
+        return Err(NftStakingError::TokenProgramNotAllowed.into());
+    }
+
+    if *stream_mint.key == stake_details.reward_token_mint
+        || stake_details.active_reward_streams().iter().any(|stream| stream.mint == *stream_mint.key)
+    {
+        msg!("The pool already pays rewards in {}", stream_mint.key);
+        return Err(NftStakingError::DuplicateRewardStream.into());
+    }
+
+    let vault = match args.funding_mode {
+        FundingMode::Mint => {
+            let mint_data = Mint::unpack(&stream_mint.data.borrow())?;
+
+            if mint_data.mint_authority != COption::Some(*stake_details_account.key) {
+                msg!("The stake details account must be the mint authority of {}", stream_mint.key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            Pubkey::default()
+        }
+        FundingMode::Vault => {
+            let vault_key = get_associated_token_address_with_program_id(
+                stake_details_account.key,
+                stream_mint.key,
+                token_program.key
+            );
+
+            if vault_key != *vault_account.key {
+                msg!("Reward Stream Vault expected : {}, received : {}", vault_key, vault_account.key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            if vault_account.data.borrow().len() == 0 {
+                let vault_ix = create_associated_token_account(
+                    creator.key,
+                    stake_details_account.key,
+                    stream_mint.key,
+                    token_program.key
+                );
+
+                invoke(
+                    &vault_ix,
+                    &[
+                        creator.clone(),
+                        vault_account.clone(),
+                        stake_details_account.clone(),
+                        stream_mint.clone(),
+                        system_program.clone(),
+                        token_program.clone(),
+                        associated_token_program.clone(),
+                    ]
+                )?;
+
+                msg!("Successfully Created the Reward Stream Vault");
+            }
+
+            vault_key
+        }
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+
+    stake_details.reward_streams[stream_count] = RewardStream {
+        mint: *stream_mint.key,
+        funding_mode: args.funding_mode,
+        vault,
+        reward_rate: args.reward_rate,
+        reward_period: args.reward_period,
+        start_time: args.start_time.max(now),
+    };
+    stake_details.reward_stream_count += 1;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Added reward stream {} for {}", stream_count, stream_mint.key);
+
+    Ok(())
+}
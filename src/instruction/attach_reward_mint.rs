@@ -0,0 +1,88 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{load_protocol_config, RewardMintSetup}};
+
+/// Gives a points-only pool its reward token. From then on rewards are minted,
+/// and the points earned so far can be converted with ConvertPoints.
+pub fn attach_reward_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer || !creator.is_writable {
+        msg!("Creator account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let reward_mint = next_account_info(iter)?;
+
+    if !reward_mint.is_writable {
+        msg!("Reward Mint is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let treasury_token_account = next_account_info(iter)?;
+
+    if !treasury_token_account.is_writable {
+        msg!("Treasury Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let fee_treasury_account = next_account_info(iter)?;
+    let protocol_config_account = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let associated_token_program = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can attach the reward mint", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    if !stake_details.is_points_only() {
+        msg!("The pool already has the reward mint {}", stake_details.reward_token_mint);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let protocol_config = load_protocol_config(program_id, protocol_config_account)?;
+
+    let reward_mint_setup = RewardMintSetup {
+        reward_mint,
+        treasury_token_account,
+        fee_treasury_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    };
+
+    stake_details.treasury = reward_mint_setup.validate(&protocol_config, stake_details_account.key)?;
+    stake_details.reward_token_mint = *reward_mint.key;
+    stake_details.fee_treasury = *fee_treasury_account.key;
+    stake_details.reward_token_program = *token_program.key;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    reward_mint_setup.attach(creator, stake_details_account)?;
+
+    msg!("Attached the reward mint {}", reward_mint.key);
+
+    Ok(())
+}
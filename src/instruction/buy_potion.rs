@@ -0,0 +1,133 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::instruction::burn;
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::load_stake_for_holder};
+
+/// Burns the potion price from the reward token account of the holder and boosts the stake
+/// until the potion expires. The rewards accrued before keep their previous multiplier.
+pub fn buy_potion(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User Account is Not Signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let user_reward_token_account = next_account_info(iter)?;
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_mint_account = next_account_info(iter)?;
+    if !reward_mint_account.is_writable {
+        msg!("Reward Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    if stake_data.is_unbonding() {
+        msg!("Stake is unbonding until {}, it can not be boosted", stake_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
+
+    if stake_details_data.is_points_only() {
+        msg!("The pool has no reward token to burn yet");
+        return Err(NftStakingError::PointsOnlyPool.into());
+    }
+
+    if stake_details_data.potion_price == 0 {
+        msg!("The pool does not sell potions");
+        return Err(NftStakingError::PotionUnavailable.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if stake_data.is_potion_active(now) {
+        msg!("The current potion expires at {}", stake_data.potion_expires_at);
+        return Err(NftStakingError::PotionActive.into());
+    }
+
+    if stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_reward_ata = get_associated_token_address_with_program_id(
+        user_account.key,
+        reward_mint_account.key,
+        token_program.key
+    );
+
+    if user_reward_ata != *user_reward_token_account.key {
+        msg!("Reward Token Account expected : {}, received : {}", user_reward_ata, user_reward_token_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    msg!("Burning {} reward tokens for the potion", stake_details_data.potion_price);
+
+    invoke(
+        &burn(
+            token_program.key,
+            user_reward_token_account.key,
+            reward_mint_account.key,
+            user_account.key,
+            &[user_account.key],
+            stake_details_data.potion_price
+        )?,
+        &[user_reward_token_account.clone(), reward_mint_account.clone(), user_account.clone(), token_program.clone()]
+    )?;
+
+    // the expired potion is accounted for up to now before the new one starts
+    stake_data.checkpoint_rewards(&stake_details_data, now)?;
+
+    stake_data.potion_boost_bps = stake_details_data.potion_boost_bps;
+    stake_data.potion_expires_at = now.checked_add(stake_details_data.potion_duration).ok_or(NftStakingError::RewardOverflow)?;
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("Potion of {} bps active until {}", stake_data.potion_boost_bps, stake_data.potion_expires_at);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{load_stake_for_holder, load_staker}};
+
+/// Cancels a requested unstake, the rewards accrue again from now on. The unbonding window is skipped: it is
+/// neither rewarded nor counted in the staked duration of the position.
+pub fn cancel_unstake(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User Account is Not Signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let staker_account = next_account_info(iter)?; // set bonus aggregate of the staker of the position
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let mut stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    if !stake_data.is_unbonding() {
+        msg!("No unstake was requested for the stake account : {}", stake_account.key);
+        return Err(NftStakingError::UnstakeNotRequested.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    let mut staker = load_staker(program_id, staker_account, stake_details_account, &stake_data.staker)?;
+
+    // the position joins the set again unless its receipt was sold, the bonus accrued meanwhile is not owed to it
+    staker.update_boost_index(now)?;
+    stake_data.leave_set_if_sold(user_account.key);
+
+    let staked_count = if stake_data.left_set {
+        staker.staked_count
+    } else {
+        staker.staked_count.checked_add(1).ok_or(NftStakingError::RewardOverflow)?
+    };
+    staker.set_staked_count(&stake_details_data, staked_count);
+    staker.serialize(&mut *staker_account.data.borrow_mut())?;
+
+    // request_unstake settled the rewards, so staked_at is the start of the unbonding window. The window is not
+    // rewarded, and initial_staked_at moves past it for the early unstake penalty, the milestones and the levels
+    let unbonding_window = now.saturating_sub(stake_data.staked_at);
+    stake_data.initial_staked_at = stake_data.initial_staked_at.saturating_add(unbonding_window);
+    stake_data.staked_at = now;
+    stake_data.set_locked_boost(&stake_details_data, stake_data.locked_boost);
+    stake_data.unbonding_ends_at = 0;
+    stake_data.boost_index_snapshot = staker.boost_index;
+    stake_data.revenue_index_snapshot = stake_details_data.revenue_per_share;
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    stake_details_data.total_staked = stake_details_data.total_staked.checked_add(1).ok_or(NftStakingError::RewardOverflow)?;
+    stake_details_data.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Unstake cancelled, rewards accrue again from {}", now);
+
+    Ok(())
+}
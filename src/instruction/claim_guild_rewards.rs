@@ -0,0 +1,113 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+use spl_token::state::Account;
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{load_guild, RewardMinter}};
+
+/// Splits the rewards credited to a guild among its members by weight, anyone can distribute them.
+/// The accounts end with a reward token account of every member, in the order of the members, or in a
+/// vesting pool with the vesting escrow of every member, created with the rent paid by the payer if needed.
+/// The rounding remainder stays in the guild for the next distribution.
+pub fn claim_guild_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let guild_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let reward_mint_account = next_account_info(iter)?;
+
+    if !reward_mint_account.is_writable {
+        msg!("Reward Mint Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let payer = next_account_info(iter)?;
+
+    if !payer.is_signer || !payer.is_writable {
+        msg!("Payer account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let system_program = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    // the balance of a points-only pool is paid in reward tokens once the pool has a mint
+    if stake_details_data.is_points_only() {
+        msg!("The pool has no reward mint to pay the guild in yet");
+        return Err(NftStakingError::PointsOnlyPool.into());
+    }
+
+    if stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
+
+    let mut guild = load_guild(program_id, guild_account, stake_details_account, guild_account.key)?;
+
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account: reward_mint_account, // unused, the fee was taken when the rewards were credited
+        rent_payer: None,
+    };
+
+    let mut distributed_amount = 0;
+
+    for member in guild.active_members() {
+
+        let member_reward_account = next_account_info(iter)?;
+
+        if !member_reward_account.is_writable {
+            msg!("Member Reward Token Account is not writable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let member_amount = guild.member_amount(member);
+
+        // the vesting escrow is checked when credited
+        if stake_details_data.is_vesting() {
+            if member_amount > 0 {
+                minter.vest_rewards(payer, &member.wallet, member_reward_account, member_amount)?;
+                distributed_amount += member_amount;
+            }
+
+            continue;
+        }
+
+        let member_reward_data = Account::unpack(&member_reward_account.data.borrow())?;
+
+        if member_reward_data.mint != stake_details_data.reward_token_mint || member_reward_data.owner != member.wallet {
+            msg!("Guild rewards must be paid to a reward token account of {}", member.wallet);
+            return Err(NftStakingError::RewardMintMismatch.into());
+        }
+
+        if member_amount > 0 {
+            minter.mint_to(member_reward_account, member_amount)?;
+            distributed_amount += member_amount;
+        }
+    }
+
+    guild.reward_balance -= distributed_amount;
+
+    guild.serialize(&mut *guild_account.data.borrow_mut())?;
+
+    msg!("Distributed {} guild rewards, {} left", distributed_amount, guild.reward_balance);
+
+    Ok(())
+}
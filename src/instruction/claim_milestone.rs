@@ -0,0 +1,169 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{
+    instructions::{CreateV1CpiBuilder, MintV1CpiBuilder, VerifyCollectionV1CpiBuilder},
+    types::{Collection, PrintSupply, TokenStandard},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::load_stake_for_holder};
+
+#[derive(BorshDeserialize)]
+pub struct ClaimMilestoneArgs {
+    pub milestone_index: u8,
+}
+
+/// Mints the NFT of a milestone to the holder of the stake receipt, once the position
+/// has been staked long enough. Each milestone can be claimed once per stake.
+pub fn claim_milestone(program_id: &Pubkey, accounts: &[AccountInfo], args: ClaimMilestoneArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    // holder of the stake receipt, pays for the milestone NFT
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer || !user_account.is_writable {
+        msg!("User Account is Not Signer or Writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    // isSigner, new mint of the milestone NFT
+    let milestone_mint = next_account_info(iter)?;
+    let milestone_metadata = next_account_info(iter)?;
+    let milestone_master_edition = next_account_info(iter)?;
+
+    // ata of the user for the milestone NFT
+    let user_milestone_account = next_account_info(iter)?;
+
+    let collection_mint = next_account_info(iter)?;
+    let collection_metadata = next_account_info(iter)?;
+    let collection_master_edition = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+    let sysvar_instructions = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let associated_token_program = next_account_info(iter)?;
+    let token_metadata_program = next_account_info(iter)?;
+
+    if *token_metadata_program.key != mpl_token_metadata::ID {
+        msg!("Token Metadata Program expected : {}, received : {}", mpl_token_metadata::ID, token_metadata_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    let index = args.milestone_index as usize;
+
+    let milestone = stake_details_data.active_milestones().get(index).ok_or_else(|| {
+        msg!("The pool has no milestone {}", index);
+        NftStakingError::InvalidMilestone
+    })?;
+
+    if stake_data.is_milestone_claimed(index) {
+        msg!("Milestone {} was already claimed for the stake", index);
+        return Err(NftStakingError::MilestoneAlreadyClaimed.into());
+    }
+
+    if stake_data.is_unbonding() {
+        msg!("Stake is unbonding until {}, milestones can not be claimed", stake_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
+
+    let staked_for = Clock::get()?.unix_timestamp - stake_data.initial_staked_at;
+
+    if staked_for < milestone.duration {
+        msg!("Staked for {} seconds, milestone {} is reached after {} seconds", staked_for, index, milestone.duration);
+        return Err(NftStakingError::MilestoneNotReached.into());
+    }
+
+    if stake_details_data.milestone_collection != *collection_mint.key {
+        msg!("Milestone Collection expected : {}, received : {}", stake_details_data.milestone_collection, collection_mint.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_details_seeds = stake_details_data.signer_seeds();
+
+    CreateV1CpiBuilder::new(token_metadata_program)
+        .metadata(milestone_metadata)
+        .master_edition(Some(milestone_master_edition))
+        .mint(milestone_mint, true)
+        .authority(stake_details_account)
+        .payer(user_account)
+        .update_authority(stake_details_account, true)
+        .system_program(system_program)
+        .sysvar_instructions(sysvar_instructions)
+        .spl_token_program(Some(token_program))
+        .name(milestone.name())
+        .uri(milestone.uri())
+        .seller_fee_basis_points(0)
+        .token_standard(TokenStandard::NonFungible)
+        .collection(Collection { verified: false, key: *collection_mint.key })
+        .print_supply(PrintSupply::Zero)
+        .invoke_signed(&[&stake_details_seeds])?;
+
+    MintV1CpiBuilder::new(token_metadata_program)
+        .token(user_milestone_account)
+        .token_owner(Some(user_account))
+        .metadata(milestone_metadata)
+        .master_edition(Some(milestone_master_edition))
+        .mint(milestone_mint)
+        .authority(stake_details_account)
+        .payer(user_account)
+        .system_program(system_program)
+        .sysvar_instructions(sysvar_instructions)
+        .spl_token_program(token_program)
+        .spl_ata_program(associated_token_program)
+        .amount(1)
+        .invoke_signed(&[&stake_details_seeds])?;
+
+    VerifyCollectionV1CpiBuilder::new(token_metadata_program)
+        .authority(stake_details_account)
+        .metadata(milestone_metadata)
+        .collection_mint(collection_mint)
+        .collection_metadata(Some(collection_metadata))
+        .collection_master_edition(Some(collection_master_edition))
+        .system_program(system_program)
+        .sysvar_instructions(sysvar_instructions)
+        .invoke_signed(&[&stake_details_seeds])?;
+
+    msg!("Minted the NFT of milestone {} : {}", index, milestone_mint.key);
+
+    stake_data.milestones_claimed |= 1 << index;
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    Ok(())
+}
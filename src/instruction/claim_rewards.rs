@@ -1,21 +1,80 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar};
+use solana_program::{account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar};
 use spl_associated_token_account::get_associated_token_address_with_program_id;
-use spl_token::{instruction::mint_to_checked, state::Mint};
 
-use crate::{errors::NftStakingError, state::{Stake, StakeDetails}};
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{create_reward_ata, is_writable_or_unused, load_stake_for_holder, load_staker, pay_referral, pay_revenue_share, pay_reward_streams, RewardMinter}};
 
-pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> ProgramResult {
+/// Who authorizes a claim, the rewards of a claim not signed by the holder only go to reward token accounts.
+enum ClaimAuthority<'b, 'a> {
+    Holder { sponsored: bool }, // a sponsored claim passes the payer of the rent after the associated token program
+    Crank(&'b AccountInfo<'a>), // reward token account of the cranker, which receives the crank tip
+    Delegate(&'b AccountInfo<'a>), // claim delegate of the position
+}
+
+#[derive(BorshDeserialize, Default)]
+pub struct ClaimRewardsArgs {
+    pub sponsored: bool, // the rent of the accounts created for the holder is paid by the payer passed after the associated token program
+}
+
+pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo], args: ClaimRewardsArgs) -> ProgramResult {
+    claim(program_id, accounts_info, ClaimAuthority::Holder { sponsored: args.sponsored })
+}
+
+/// Claims the rewards of a position for its holder, without the signature of the holder. The accounts start with
+/// the cranker and its reward token account, which receives the crank tip of the pool, followed by the accounts of
+/// `claim_rewards`. The rewards are only paid to the reward token accounts of the holder.
+pub fn crank_claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> ProgramResult {
 
     let iter = &mut accounts_info.iter();
 
+    let cranker_account = next_account_info(iter)?;
+
+    if !cranker_account.is_signer {
+        msg!("Cranker account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let cranker_reward_token_account = next_account_info(iter)?;
+
+    if !cranker_reward_token_account.is_writable {
+        msg!("Cranker Reward Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    claim(program_id, iter.as_slice(), ClaimAuthority::Crank(cranker_reward_token_account))
+}
+
+/// Claims the rewards of a position as its claim delegate. The accounts start with the delegate, followed by
+/// the accounts of `claim_rewards`. The rewards are paid to the destination registered with the delegate,
+/// or to the reward token accounts of the holder.
+pub fn delegated_claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts_info.iter();
+
+    let delegate_account = next_account_info(iter)?;
+
+    if !delegate_account.is_signer {
+        msg!("Delegate account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    claim(program_id, iter.as_slice(), ClaimAuthority::Delegate(delegate_account))
+}
+
+fn claim<'a>(program_id: &Pubkey, accounts_info:&[AccountInfo<'a>], authority: ClaimAuthority<'_, 'a>) -> ProgramResult {
+
+    let iter = &mut accounts_info.iter();
+
+    // holder of the stake receipt
     let user_account = next_account_info(iter)?;
 
-    if !user_account.is_signer {
+    if !user_account.is_signer && matches!(authority, ClaimAuthority::Holder { .. }) {
         msg!("User account is not a signer");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    let user_receipt_account = next_account_info(iter)?;
+
     let stake_account = next_account_info(iter)?;
     
     if !stake_account.is_writable {
@@ -27,6 +86,8 @@ pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> Progr
         msg!("Stake account is not owned by the program");
         return Err(ProgramError::InvalidAccountData);
     }
+
+    let staker_account = next_account_info(iter)?; // set bonus aggregate of the staker of the position
     
     let user_reward_token_account = next_account_info(iter)?;
     
@@ -37,7 +98,7 @@ pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> Progr
 
     let reward_mint_account = next_account_info(iter)?;
     
-    if !reward_mint_account.is_writable {
+    if !is_writable_or_unused(reward_mint_account) {
         msg!("Reward Mint Account is not writable");
         return Err(ProgramError::InvalidAccountData);
     }
@@ -45,8 +106,36 @@ pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> Progr
     let stake_details_account = next_account_info(iter)?;
     
     let nft_mint_account = next_account_info(iter)?;
+
+    let fee_treasury_account = next_account_info(iter)?;
+
+    if !is_writable_or_unused(fee_treasury_account) {
+        msg!("Fee Treasury Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let token_program = next_account_info(iter)?;
 
+    let revenue_vault_account = next_account_info(iter)?; // lamports of the SOL revenue share of the pool
+
+    let system_program = next_account_info(iter)?; // creates the vesting escrow of the holder in a vesting pool
+
+    let associated_token_program = next_account_info(iter)?; // creates the reward ata of the holder if missing
+
+    let rent_payer = match authority {
+        ClaimAuthority::Holder { sponsored: true } => {
+            let payer_account = next_account_info(iter)?;
+
+            if !payer_account.is_signer || !payer_account.is_writable {
+                msg!("Payer account is not signer or writable");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            Some(payer_account)
+        }
+        _ => None,
+    };
+
     if stake_account.owner != program_id {
         msg!("Stake Account not owned by the program");
         return Err(ProgramError::InvalidAccountOwner);
@@ -57,93 +146,125 @@ pub fn claim_rewards(program_id: &Pubkey, accounts_info:&[AccountInfo]) -> Progr
         return Err(ProgramError::InvalidAccountOwner);
     }
 
-    let seeds = [
-        b"stake", 
-        stake_details_account.key.as_ref(), 
-        nft_mint_account.key.as_ref(), 
-        user_account.key.as_ref()
-    ];
-
-    let(stake_account_key, _bump) = Pubkey::find_program_address(&seeds, program_id);
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
 
-    if stake_account_key != *stake_account.key {
-        msg!("Stake Account Mismatch !");
-        msg!("Expected : {}, received : {}", stake_account_key, stake_account.key);
-        return Err(ProgramError::InvalidAccountData);
+    // the pool signs for the mints it controls, a payout must only mint its reward mint
+    if !stake_details_data.is_points_only() && stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
     }
 
-    if stake_account.data.borrow().len() == 0 {
-        msg!("Stake Account is not initialized");
-        return Err(NftStakingError::AccountNotInitialized.into());
-    }
+    let mut stake_account_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
 
-    let mut stake_account_data = Stake::try_from_slice(&*stake_account.data.borrow())?;
+    // settled before the rewards are paid, a buyer does not pay the guild of the seller
+    stake_account_data.leave_guild_if_sold(user_account.key);
 
-    let user_reward_ata = get_associated_token_address_with_program_id(
-        user_account.key,
-        reward_mint_account.key, 
-        token_program.key
-    );
+    if stake_account_data.is_unbonding() {
+        msg!("Stake is unbonding until {}, no rewards are accrued", stake_account_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
 
-    if user_reward_ata != *user_reward_token_account.key{
-        msg!("Invalid Reward Token Account");
-        msg!("Expected : {}, received : {}", user_reward_ata, user_reward_token_account.key);
-        return Err(ProgramError::InvalidAccountData);
+    if let ClaimAuthority::Delegate(delegate_account) = authority {
+        if !stake_account_data.is_claim_delegate(delegate_account.key, user_account.key) {
+            msg!("{} is not the claim delegate of {} for this position", delegate_account.key, user_account.key);
+            return Err(NftStakingError::InvalidClaimDelegate.into());
+        }
     }
 
-    if stake_account_data.reward_mint_ata != *user_reward_token_account.key {
-        msg!("Reward Token Account Mismatch !");
-        msg!("Expected : {}, received : {}", stake_account_data.reward_mint_ata, user_reward_token_account.key);
-        return Err(ProgramError::InvalidAccountData);
+    // points, vesting escrows and guilds would need the signature of the holder to be created or are not its accounts
+    if !matches!(authority, ClaimAuthority::Holder { .. }) && (stake_details_data.is_points_only() || stake_details_data.is_vesting() || stake_account_data.is_guild_position()) {
+        msg!("Claims not signed by the holder only pay rewards to reward token accounts");
+        return Err(NftStakingError::CrankUnsupported.into());
     }
 
-    let reward_mint_data = Mint::unpack(&*reward_mint_account.data.borrow())?;
+    // the rewards go to the current holder of the receipt, points, vesting escrows and guilds are checked when credited
+    if !stake_details_data.is_points_only() && !stake_details_data.is_vesting() && !stake_account_data.is_guild_position() {
+        let user_reward_ata = get_associated_token_address_with_program_id(
+            user_account.key,
+            reward_mint_account.key, 
+            token_program.key
+        );
+
+        let expected_reward_account = match authority {
+            ClaimAuthority::Delegate(_) if stake_account_data.claim_destination != Pubkey::default() => {
+                stake_account_data.claim_destination
+            }
+            _ => stake_account_data.reward_destination(user_account.key, user_reward_ata),
+        };
+
+        if expected_reward_account != *user_reward_token_account.key{
+            msg!("Invalid Reward Token Account");
+            msg!("Expected : {}, received : {}", expected_reward_account, user_reward_token_account.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // the holder, or its sponsor, pays the rent of its ata, claims not signed by it need an existing account
+        if expected_reward_account == user_reward_ata && matches!(authority, ClaimAuthority::Holder { .. }) {
+            create_reward_ata(rent_payer.unwrap_or(user_account), user_account, user_reward_token_account, reward_mint_account, token_program, associated_token_program, system_program)?;
+        }
+    }
 
     let current_time = Clock::get()?.unix_timestamp;
 
+    let mut staker = load_staker(program_id, staker_account, stake_details_account, &stake_account_data.staker)?;
+    staker.update_boost_index(current_time)?;
+
     let staked_duration = current_time - stake_account_data.staked_at;
-    let reward_token_amount = (staked_duration * 1000) as u64; // 1000 reward tokens released per second 
+    let settled = stake_account_data.settle_rewards(&stake_details_data, &staker, current_time)?;
+
+    // the first settlement of a buyer takes the position out of the set of the seller, the current tiers apply from now on
+    let staked_count = if stake_account_data.leave_set_if_sold(user_account.key) {
+        staker.staked_count.saturating_sub(1)
+    } else {
+        staker.staked_count
+    };
+    staker.set_staked_count(&stake_details_data, staked_count);
 
     msg!("Staked Duration is {} seconds", staked_duration);
-    msg!("minting {} tokens to {}",reward_token_amount, user_reward_token_account.key);
-
-    let ix = mint_to_checked(
-        token_program.key, 
-        reward_mint_account.key, 
-        user_reward_token_account.key, 
-        stake_details_account.key,     // as stake_details is the mint authority of the reward token
-        &[stake_details_account.key], // as the stake details can only mint the reward tokens 
-        reward_token_amount, 
-        reward_mint_data.decimals,
-    )?;
 
-    let stake_details_data = StakeDetails::try_from_slice(&*stake_details_account.data.borrow())?;
-
-    let signers_seeds = [
-        b"stake_details",
-        stake_details_data.creator.as_ref(),
-        stake_details_data.collection_mint.as_ref(),
-        &[stake_details_data.bump_seed]
-    ];
-
-    invoke_signed(
-        &ix, 
-        &[
-            stake_details_account.clone(),
-            user_reward_token_account.clone(),
-            reward_mint_account.clone(),
-        ], 
-        &[&signers_seeds]
-    )?;
+    // written first, the holder may earn points on the same account
+    staker.serialize(&mut *staker_account.data.borrow_mut())?;
+
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account,
+        rent_payer,
+    };
+
+    match authority {
+        ClaimAuthority::Crank(cranker_reward_token_account) => {
+            minter.pay_cranked_rewards(user_reward_token_account, cranker_reward_token_account, settled.reward_amount)?
+        }
+        ClaimAuthority::Holder { .. } | ClaimAuthority::Delegate(_) => {
+            minter.pay_position_rewards(&stake_account_data, user_account, user_reward_token_account, settled.reward_amount)?
+        }
+    }
+
+    // the remaining accounts pay out the extra reward streams of the pool
+    pay_reward_streams(&minter, &mut stake_account_data, &settled.stream_amounts, user_account.key, iter)?;
 
-    // now reset the staked_at
+    // the referral accounts follow the reward stream accounts
+    pay_referral(&minter, user_account, &staker, settled.share(stake_details_data.referral_bps), iter)?;
 
-    let now = Clock::get()?.unix_timestamp;
+    pay_revenue_share(user_account, revenue_vault_account, &stake_details_data, &mut stake_account_data)?;
 
-    stake_account_data.staked_at = now;
+    // staked_at is reset and the remainder is carried over to the next claim
     stake_account_data.serialize(&mut *stake_account.data.borrow_mut())?;
 
-    msg!("Successfully reset the staked_at to : {}", now);
+    msg!("Successfully reset the staked_at to : {}, carrying {} to the next claim", current_time, stake_account_data.reward_carry);
 
     Ok(())  
 }
\ No newline at end of file
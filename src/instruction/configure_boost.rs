@@ -0,0 +1,72 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{BoostTier, StakeDetails, MAX_BOOST_TIERS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureBoostArgs {
+    pub tiers: Vec<BoostTier>, // sorted by min_locked
+}
+
+/// Sets the boost curve, the extra rewards a position earns by locking reward tokens.
+/// A change applies to each position from its next claim, boost change or unstake, never to the time before.
+pub fn configure_boost(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureBoostArgs) -> ProgramResult {
+
+    if args.tiers.len() > MAX_BOOST_TIERS {
+        msg!("A pool can have up to {} boost tiers, received : {}", MAX_BOOST_TIERS, args.tiers.len());
+        return Err(NftStakingError::InvalidBoost.into());
+    }
+
+    let mut tiers = [BoostTier::default(); MAX_BOOST_TIERS];
+    let mut previous_tier = BoostTier::default();
+
+    for (index, tier) in args.tiers.iter().enumerate() {
+
+        if tier.min_locked <= previous_tier.min_locked || tier.boost_bps <= previous_tier.boost_bps {
+            msg!("Boost tiers must lock and boost more than the previous tier, received : {:?}", tier);
+            return Err(NftStakingError::InvalidBoost.into());
+        }
+
+        tiers[index] = *tier;
+        previous_tier = *tier;
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the boost", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.boost_tier_count = args.tiers.len() as u8;
+    stake_details.boost_tiers = tiers;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Configured {} boost tiers", stake_details.boost_tier_count);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{StakeDetails, MAX_CRANK_TIP_BPS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureCrankTipArgs {
+    pub tip_bps: u16, // share of the rewards claimed by a crank paid to the cranker
+}
+
+/// Sets the tip the pool pays to whoever cranks the claims of its stakers.
+pub fn configure_crank_tip(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureCrankTipArgs) -> ProgramResult {
+
+    if args.tip_bps > MAX_CRANK_TIP_BPS {
+        msg!("Crank tip must be at most {} bps", MAX_CRANK_TIP_BPS);
+        return Err(NftStakingError::InvalidCrankTip.into());
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the crank tip", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.crank_tip_bps = args.tip_bps;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Crank tip : {} bps", args.tip_bps);
+
+    Ok(())
+}
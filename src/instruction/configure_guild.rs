@@ -0,0 +1,112 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::{Guild, GuildMember, GUILD_RESERVED_LEN, MAX_GUILD_MEMBERS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureGuildArgs {
+    pub members: Vec<GuildMember>,
+}
+
+/// Creates the guild of the authority in the pool or replaces its members.
+/// The rewards credited to the guild must be distributed to the current members first.
+pub fn configure_guild(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureGuildArgs) -> ProgramResult {
+
+    if args.members.is_empty() || args.members.len() > MAX_GUILD_MEMBERS {
+        msg!("A guild must have 1 to {} members, received : {}", MAX_GUILD_MEMBERS, args.members.len());
+        return Err(NftStakingError::InvalidGuild.into());
+    }
+
+    let mut members = [GuildMember::default(); MAX_GUILD_MEMBERS];
+
+    for (index, member) in args.members.iter().enumerate() {
+
+        if member.weight == 0 || args.members[..index].iter().any(|other| other.wallet == member.wallet) {
+            msg!("Guild member {} must have a weight and be listed once", member.wallet);
+            return Err(NftStakingError::InvalidGuild.into());
+        }
+
+        members[index] = *member;
+    }
+
+    let iter = &mut accounts.iter();
+
+    let authority = next_account_info(iter)?;
+
+    if !authority.is_signer || !authority.is_writable {
+        msg!("Authority account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let guild_account = next_account_info(iter)?; // pda
+
+    if !guild_account.is_writable {
+        msg!("Guild Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let system_program = next_account_info(iter)?;
+
+    let (guild_key, guild_bump) = Pubkey::find_program_address(
+        &[b"guild", stake_details_account.key.as_ref(), authority.key.as_ref()],
+        program_id
+    );
+
+    if guild_key != *guild_account.key {
+        msg!("Guild Account expected : {}, received : {}", guild_key, guild_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut guild = if guild_account.data.borrow().len() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                guild_account.key,
+                Rent::get()?.minimum_balance(Guild::LEN),
+                Guild::LEN as u64,
+                program_id
+            ),
+            &[authority.clone(), guild_account.clone(), system_program.clone()],
+            &[&[b"guild", stake_details_account.key.as_ref(), authority.key.as_ref(), &[guild_bump]]]
+        )?;
+
+        msg!("Guild account has been created");
+
+        Guild {
+            stake_details_key: *stake_details_account.key,
+            authority: *authority.key,
+            member_count: 0,
+            members,
+            reward_balance: 0,
+            bump_seed: guild_bump,
+            reserved: [0; GUILD_RESERVED_LEN],
+        }
+    } else {
+        let guild = Guild::try_from_slice(&guild_account.data.borrow())?;
+
+        if guild.has_undistributed_rewards() {
+            msg!("Guild {} still holds {} rewards of its current members", guild_key, guild.reward_balance);
+            return Err(NftStakingError::GuildRewardsNotDistributed.into());
+        }
+
+        guild
+    };
+
+    guild.member_count = args.members.len() as u8;
+    guild.members = members;
+
+    guild.serialize(&mut *guild_account.data.borrow_mut())?;
+
+    msg!("Guild {} has {} members, total weight : {}", guild_key, guild.member_count, guild.total_weight());
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{Level, StakeDetails, MAX_LEVELS}};
+
+#[derive(BorshDeserialize)]
+pub struct LevelArgs {
+    pub duration: i64, // in seconds of continuous staking
+    pub uri: String,
+}
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureLevelsArgs {
+    pub levels: Vec<LevelArgs>, // sorted by duration
+}
+
+/// Sets the levels staked NFTs of the pool evolve through. The levels can be changed later on,
+/// stakes keep the level they reached until they evolve again.
+pub fn configure_levels(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureLevelsArgs) -> ProgramResult {
+
+    if args.levels.len() > MAX_LEVELS {
+        msg!("A pool can have up to {} levels, received : {}", MAX_LEVELS, args.levels.len());
+        return Err(NftStakingError::InvalidLevel.into());
+    }
+
+    let mut levels = [Level::default(); MAX_LEVELS];
+    let mut previous_duration = 0;
+
+    for (index, level) in args.levels.iter().enumerate() {
+
+        if level.duration <= previous_duration {
+            msg!("Level durations must be positive and increasing, received : {}", level.duration);
+            return Err(NftStakingError::InvalidLevel.into());
+        }
+
+        levels[index] = Level::new(level.duration, &level.uri).ok_or(NftStakingError::InvalidLevel)?;
+
+        previous_duration = level.duration;
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the levels", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.level_count = args.levels.len() as u8;
+    stake_details.levels = levels;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Configured {} levels", stake_details.level_count);
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{
+    instructions::{CreateV1CpiBuilder, MintV1CpiBuilder},
+    types::{CollectionDetails, PrintSupply, TokenStandard},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{Milestone, StakeDetails, MAX_MILESTONES}};
+
+#[derive(BorshDeserialize)]
+pub struct MilestoneArgs {
+    pub duration: i64, // in seconds of continuous staking
+    pub name: String,
+    pub uri: String,
+}
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureMilestonesArgs {
+    pub collection_name: String,
+    pub collection_uri: String,
+    pub milestones: Vec<MilestoneArgs>, // sorted by duration
+}
+
+/// Sets the staking milestones of a pool and creates the collection of the milestone NFTs,
+/// with the stake details pda as its update authority. Milestones can only be configured once,
+/// so a claimed milestone always refers to the same badge.
+pub fn configure_milestones(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureMilestonesArgs) -> ProgramResult {
+
+    if args.milestones.is_empty() || args.milestones.len() > MAX_MILESTONES {
+        msg!("A pool can have 1 to {} milestones, received : {}", MAX_MILESTONES, args.milestones.len());
+        return Err(NftStakingError::InvalidMilestone.into());
+    }
+
+    let mut milestones = [Milestone::default(); MAX_MILESTONES];
+    let mut previous_duration = 0;
+
+    for (index, milestone) in args.milestones.iter().enumerate() {
+
+        if milestone.duration <= previous_duration {
+            msg!("Milestone durations must be positive and increasing, received : {}", milestone.duration);
+            return Err(NftStakingError::InvalidMilestone.into());
+        }
+
+        milestones[index] = Milestone::new(milestone.duration, &milestone.name, &milestone.uri)
+            .ok_or(NftStakingError::InvalidMilestone)?;
+
+        previous_duration = milestone.duration;
+    }
+
+    let iter = &mut accounts.iter();
+
+    // isSigner and isWritable, pays for the collection
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer || !creator.is_writable {
+        msg!("Creator account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // pda, mint of the milestone collection NFT
+    let collection_mint = next_account_info(iter)?;
+    let collection_metadata = next_account_info(iter)?;
+    let collection_master_edition = next_account_info(iter)?;
+
+    // ata of the stake details account holding the collection NFT
+    let collection_token_account = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+    let sysvar_instructions = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let associated_token_program = next_account_info(iter)?;
+    let token_metadata_program = next_account_info(iter)?;
+
+    if *token_metadata_program.key != mpl_token_metadata::ID {
+        msg!("Token Metadata Program expected : {}, received : {}", mpl_token_metadata::ID, token_metadata_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the milestones", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    if stake_details.milestone_count != 0 {
+        msg!("The milestones of the pool are already configured");
+        return Err(NftStakingError::MilestonesAlreadyConfigured.into());
+    }
+
+    let (collection_mint_key, collection_mint_bump) = Pubkey::find_program_address(
+        &[b"milestone_collection", stake_details_account.key.as_ref()],
+        program_id
+    );
+
+    if collection_mint_key != *collection_mint.key {
+        msg!("Milestone Collection Mint expected : {}, received : {}", collection_mint_key, collection_mint.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_details_seeds = stake_details.signer_seeds();
+    let collection_mint_seeds: [&[u8]; 3] = [
+        b"milestone_collection",
+        stake_details_account.key.as_ref(),
+        &[collection_mint_bump],
+    ];
+
+    CreateV1CpiBuilder::new(token_metadata_program)
+        .metadata(collection_metadata)
+        .master_edition(Some(collection_master_edition))
+        .mint(collection_mint, true)
+        .authority(stake_details_account)
+        .payer(creator)
+        .update_authority(stake_details_account, true)
+        .system_program(system_program)
+        .sysvar_instructions(sysvar_instructions)
+        .spl_token_program(Some(token_program))
+        .name(args.collection_name)
+        .uri(args.collection_uri)
+        .seller_fee_basis_points(0)
+        .token_standard(TokenStandard::NonFungible)
+        .collection_details(CollectionDetails::V1 { size: 0 })
+        .print_supply(PrintSupply::Zero)
+        .invoke_signed(&[&stake_details_seeds, &collection_mint_seeds])?;
+
+    MintV1CpiBuilder::new(token_metadata_program)
+        .token(collection_token_account)
+        .token_owner(Some(stake_details_account))
+        .metadata(collection_metadata)
+        .master_edition(Some(collection_master_edition))
+        .mint(collection_mint)
+        .authority(stake_details_account)
+        .payer(creator)
+        .system_program(system_program)
+        .sysvar_instructions(sysvar_instructions)
+        .spl_token_program(token_program)
+        .spl_ata_program(associated_token_program)
+        .amount(1)
+        .invoke_signed(&[&stake_details_seeds])?;
+
+    msg!("Successfully Created the Milestone Collection : {}", collection_mint.key);
+
+    stake_details.milestone_collection = collection_mint_key;
+    stake_details.milestone_count = args.milestones.len() as u8;
+    stake_details.milestones = milestones;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Configured {} milestones", stake_details.milestone_count);
+
+    Ok(())
+}
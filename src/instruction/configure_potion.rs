@@ -0,0 +1,60 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigurePotionArgs {
+    pub price: u64, // reward tokens burnt per potion, 0 stops the sale
+    pub boost_bps: u16, // e.g. 10_000 doubles the rewards
+    pub duration: i64, // in seconds
+}
+
+/// Sets the potion the stakers of the pool can buy. Potions already bought keep their boost and expiry.
+pub fn configure_potion(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigurePotionArgs) -> ProgramResult {
+
+    if args.duration <= 0 || (args.price > 0 && args.boost_bps == 0) {
+        msg!("A potion must have a boost and a positive duration");
+        return Err(NftStakingError::PotionUnavailable.into());
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the potion", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.potion_price = args.price;
+    stake_details.potion_boost_bps = args.boost_bps;
+    stake_details.potion_duration = args.duration;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Potion : {} bps for {} seconds at {} reward tokens", args.boost_bps, args.duration, args.price);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{StakeDetails, BASIS_POINTS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureReferralArgs {
+    pub referral_bps: u16, // share of the rewards of a referred staker paid to its referrer, 0 stops the referrals
+}
+
+/// Sets the referral share of the pool, it applies to the rewards paid from now on.
+pub fn configure_referral(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureReferralArgs) -> ProgramResult {
+
+    if args.referral_bps as u64 > BASIS_POINTS {
+        msg!("Referral share must be at most {} bps", BASIS_POINTS);
+        return Err(NftStakingError::InvalidReferral.into());
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the referrals", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.referral_bps = args.referral_bps;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Referral : {} bps", args.referral_bps);
+
+    Ok(())
+}
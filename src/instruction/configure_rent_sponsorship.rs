@@ -0,0 +1,52 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureRentSponsorshipArgs {
+    pub enabled: bool, // the rent vault pays the rent of new positions
+}
+
+/// Lets the pool sponsor the rent of new positions out of its rent vault, see `fund_rent_vault`. The payer of a stake
+/// pays the rent while the vault is short. Positions staked before keep their rent payer.
+pub fn configure_rent_sponsorship(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureRentSponsorshipArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the rent sponsorship", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.sponsors_rent = args.enabled;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Rent sponsorship : {}", args.enabled);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{SetBonusTier, StakeDetails, BASIS_POINTS, MAX_SET_BONUS_TIERS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureSetBonusArgs {
+    pub tiers: Vec<SetBonusTier>, // sorted by min_staked
+}
+
+/// Sets the bonus paid on every position of a wallet staking several NFTs of the pool.
+/// A change applies to each staker from the next time it stakes, claims or unstakes, never to the time before.
+pub fn configure_set_bonus(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureSetBonusArgs) -> ProgramResult {
+
+    if args.tiers.len() > MAX_SET_BONUS_TIERS {
+        msg!("A pool can have up to {} set bonus tiers, received : {}", MAX_SET_BONUS_TIERS, args.tiers.len());
+        return Err(NftStakingError::InvalidSetBonus.into());
+    }
+
+    let mut tiers = [SetBonusTier::default(); MAX_SET_BONUS_TIERS];
+    let mut previous_min_staked = 1; // a single NFT is not a set
+
+    for (index, tier) in args.tiers.iter().enumerate() {
+
+        if tier.min_staked <= previous_min_staked {
+            msg!("Set bonus tiers must start at 2 NFTs and increase, received : {}", tier.min_staked);
+            return Err(NftStakingError::InvalidSetBonus.into());
+        }
+
+        if tier.bonus_bps as u64 > BASIS_POINTS {
+            msg!("Set bonus can not exceed {} bps, received : {}", BASIS_POINTS, tier.bonus_bps);
+            return Err(NftStakingError::InvalidSetBonus.into());
+        }
+
+        tiers[index] = *tier;
+        previous_min_staked = tier.min_staked;
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the set bonus", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.set_bonus_tier_count = args.tiers.len() as u8;
+    stake_details.set_bonus_tiers = tiers;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Configured {} set bonus tiers", stake_details.set_bonus_tier_count);
+
+    Ok(())
+}
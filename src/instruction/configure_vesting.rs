@@ -0,0 +1,58 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::{StakeDetails, BASIS_POINTS}};
+
+#[derive(BorshDeserialize)]
+pub struct ConfigureVestingArgs {
+    pub vesting_period: i64, // in seconds, 0 pays the rewards out directly
+    pub instant_withdraw_penalty_bps: u16,
+}
+
+/// Sets how the rewards of the pool vest. Rewards already in the vesting escrows keep their schedule.
+pub fn configure_vesting(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigureVestingArgs) -> ProgramResult {
+
+    if args.vesting_period < 0 || args.instant_withdraw_penalty_bps as u64 > BASIS_POINTS {
+        msg!("Vesting period must not be negative and the penalty at most {} bps", BASIS_POINTS);
+        return Err(NftStakingError::InvalidVesting.into());
+    }
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can configure the vesting", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    stake_details.vesting_period = args.vesting_period;
+    stake_details.instant_withdraw_penalty_bps = args.instant_withdraw_penalty_bps;
+
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Vesting : {} seconds, {} bps penalty on instant withdrawal", args.vesting_period, args.instant_withdraw_penalty_bps);
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{load_staker, RewardMinter}};
+
+#[derive(BorshDeserialize)]
+pub struct ConvertPointsArgs {
+    pub amount: u64, // one point converts into one base unit of the reward token
+}
+
+/// Converts points earned while the pool was points-only into reward tokens, after the protocol fee.
+pub fn convert_points(program_id: &Pubkey, accounts: &[AccountInfo], args: ConvertPointsArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_staker_account = next_account_info(iter)?; // holds the points of the user
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let user_reward_token_account = next_account_info(iter)?;
+
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_mint_account = next_account_info(iter)?;
+
+    if !reward_mint_account.is_writable {
+        msg!("Reward Mint Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let fee_treasury_account = next_account_info(iter)?;
+
+    if !fee_treasury_account.is_writable {
+        msg!("Fee Treasury Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?; // creates the vesting escrow of the holder in a vesting pool
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details_data.is_points_only() {
+        msg!("The pool has no reward mint to convert the points into yet");
+        return Err(NftStakingError::PointsOnlyPool.into());
+    }
+
+    if stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
+
+    // a vesting escrow is checked when the rewards are vested
+    if !stake_details_data.is_vesting() {
+        let user_reward_ata = get_associated_token_address_with_program_id(
+            user_account.key,
+            reward_mint_account.key,
+            token_program.key
+        );
+
+        if user_reward_ata != *user_reward_token_account.key {
+            msg!("Reward Token Account expected : {}, received : {}", user_reward_ata, user_reward_token_account.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    let mut staker = load_staker(program_id, user_staker_account, stake_details_account, user_account.key)?;
+
+    if args.amount == 0 || args.amount > staker.points {
+        msg!("{} points can be converted, requested : {}", staker.points, args.amount);
+        return Err(NftStakingError::InsufficientPoints.into());
+    }
+
+    staker.points -= args.amount;
+    staker.serialize(&mut *user_staker_account.data.borrow_mut())?;
+
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account,
+        rent_payer: None,
+    };
+
+    minter.pay_rewards(user_account, user_reward_token_account, args.amount)?;
+
+    msg!("Converted {} points, {} left", args.amount, staker.points);
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::{StakeDetails, REVENUE_SCALE}};
+
+#[derive(BorshDeserialize)]
+pub struct DepositRevenueArgs {
+    pub amount: u64, // lamports shared among the NFTs staked at the time of the deposit
+}
+
+/// Deposits lamports into the revenue vault of the pool, anyone can deposit.
+/// Each NFT currently staked can claim an equal share of the deposit.
+pub fn deposit_revenue(program_id: &Pubkey, accounts: &[AccountInfo], args: DepositRevenueArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let depositor_account = next_account_info(iter)?;
+
+    if !depositor_account.is_signer || !depositor_account.is_writable {
+        msg!("Depositor Account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let revenue_vault_account = next_account_info(iter)?; // pda holding the deposited lamports
+
+    if !revenue_vault_account.is_writable {
+        msg!("Revenue Vault Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let system_program = next_account_info(iter)?;
+
+    let mut stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if args.amount == 0 {
+        msg!("Deposit amount must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // deposits made while nothing is staked could never be claimed
+    if stake_details_data.total_staked == 0 {
+        return Err(NftStakingError::NoStakedNfts.into());
+    }
+
+    let (revenue_vault_key, revenue_vault_bump) = Pubkey::find_program_address(
+        &[b"revenue", stake_details_account.key.as_ref()],
+        program_id
+    );
+
+    if revenue_vault_key != *revenue_vault_account.key {
+        msg!("Revenue Vault expected : {}, received : {}", revenue_vault_key, revenue_vault_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the first depositor pays the rent of the vault, the rent is never shared.
+    // The vault is assigned rather than created, so lamports sent to its address beforehand cannot block it
+    if revenue_vault_account.owner != program_id {
+        let rent_lamports = Rent::get()?.minimum_balance(0).saturating_sub(revenue_vault_account.lamports());
+
+        if rent_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(depositor_account.key, revenue_vault_account.key, rent_lamports),
+                &[depositor_account.clone(), revenue_vault_account.clone(), system_program.clone()]
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::assign(revenue_vault_account.key, program_id),
+            &[revenue_vault_account.clone(), system_program.clone()],
+            &[&[b"revenue", stake_details_account.key.as_ref(), &[revenue_vault_bump]]]
+        )?;
+
+        stake_details_data.revenue_vault = revenue_vault_key;
+
+        msg!("Revenue Vault has been created");
+    }
+
+    invoke(
+        &system_instruction::transfer(depositor_account.key, revenue_vault_account.key, args.amount),
+        &[depositor_account.clone(), revenue_vault_account.clone(), system_program.clone()]
+    )?;
+
+    let revenue_per_nft = (args.amount as u128 * REVENUE_SCALE) / stake_details_data.total_staked as u128;
+
+    stake_details_data.revenue_per_share = stake_details_data.revenue_per_share
+        .checked_add(revenue_per_nft)
+        .ok_or(NftStakingError::RewardOverflow)?;
+
+    stake_details_data.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Deposited {} lamports for {} staked NFTs", args.amount, stake_details_data.total_staked);
+
+    Ok(())
+}
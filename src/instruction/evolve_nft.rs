@@ -0,0 +1,142 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{
+    accounts::Metadata,
+    instructions::{UpdateAsDataDelegateV2CpiBuilder, UpdateAsUpdateAuthorityV2CpiBuilder},
+    types::Data,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::load_stake_for_holder};
+
+/// Evolves a staked NFT to the next level of the pool by updating its metadata uri.
+/// The stake details pda must be the update authority of the NFT, or a data delegate
+/// of its collection, in which case the delegate record has to be passed.
+pub fn evolve_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    // holder of the stake receipt, pays for a larger metadata account
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer || !user_account.is_writable {
+        msg!("User Account is Not Signer or Writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let nft_metadata_account = next_account_info(iter)?;
+
+    // collection data delegate record of the stake details pda, or the token metadata program id
+    // when the stake details pda is the update authority
+    let delegate_record = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+    let sysvar_instructions = next_account_info(iter)?;
+    let token_metadata_program = next_account_info(iter)?;
+
+    if *token_metadata_program.key != mpl_token_metadata::ID {
+        msg!("Token Metadata Program expected : {}, received : {}", mpl_token_metadata::ID, token_metadata_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    if stake_data.is_unbonding() {
+        msg!("Stake is unbonding until {}, the NFT can not evolve", stake_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
+
+    let next_level = stake_details_data.active_levels().get(stake_data.level as usize).ok_or_else(|| {
+        msg!("The NFT already reached the last level : {}", stake_data.level);
+        NftStakingError::InvalidLevel
+    })?;
+
+    let staked_for = Clock::get()?.unix_timestamp - stake_data.initial_staked_at;
+
+    if staked_for < next_level.duration {
+        msg!("Staked for {} seconds, the next level is reached after {} seconds", staked_for, next_level.duration);
+        return Err(NftStakingError::LevelNotReached.into());
+    }
+
+    let metadata = Metadata::safe_deserialize(&nft_metadata_account.data.borrow())?;
+
+    if metadata.mint != *nft_mint_account.key {
+        msg!("NFT mint key mismatch in NFT metadata Account");
+        return Err(NftStakingError::InvalidMetadataAccount.into());
+    }
+
+    // only the uri changes, the traits of the level live in the off-chain json
+    let data = Data {
+        name: metadata.name.trim_end_matches('\0').to_string(),
+        symbol: metadata.symbol.trim_end_matches('\0').to_string(),
+        uri: next_level.uri(),
+        seller_fee_basis_points: metadata.seller_fee_basis_points,
+        creators: metadata.creators,
+    };
+
+    let stake_details_seeds = stake_details_data.signer_seeds();
+
+    if *delegate_record.key == mpl_token_metadata::ID {
+        UpdateAsUpdateAuthorityV2CpiBuilder::new(token_metadata_program)
+            .authority(stake_details_account)
+            .mint(nft_mint_account)
+            .metadata(nft_metadata_account)
+            .payer(user_account)
+            .system_program(system_program)
+            .sysvar_instructions(sysvar_instructions)
+            .data(data)
+            .invoke_signed(&[&stake_details_seeds])?;
+    } else {
+        UpdateAsDataDelegateV2CpiBuilder::new(token_metadata_program)
+            .authority(stake_details_account)
+            .delegate_record(Some(delegate_record))
+            .mint(nft_mint_account)
+            .metadata(nft_metadata_account)
+            .payer(user_account)
+            .system_program(system_program)
+            .sysvar_instructions(sysvar_instructions)
+            .data(data)
+            .invoke_signed(&[&stake_details_seeds])?;
+    }
+
+    stake_data.level += 1;
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("The NFT evolved to level {}", stake_data.level);
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, pubkey::Pubkey, system_instruction
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::check_rent_vault};
+
+#[derive(BorshDeserialize)]
+pub struct RentVaultArgs {
+    pub amount: u64, // lamports moved into or out of the rent vault
+}
+
+/// Tops up the rent vault of the pool, which pays the rent of new positions while the pool sponsors it.
+/// The first deposit must cover the rent exemption of the vault itself.
+pub fn fund_rent_vault(program_id: &Pubkey, accounts: &[AccountInfo], args: RentVaultArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer || !creator.is_writable {
+        msg!("Creator account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let rent_vault_account = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+
+    let stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can fund the rent vault", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    if args.amount == 0 {
+        msg!("Deposit amount must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_rent_vault(program_id, rent_vault_account, stake_details_account)?;
+
+    invoke(
+        &system_instruction::transfer(creator.key, rent_vault_account.key, args.amount),
+        &[creator.clone(), rent_vault_account.clone(), system_program.clone()]
+    )?;
+
+    msg!("Deposited {} lamports, the rent vault holds {}", args.amount, rent_vault_account.lamports());
+
+    Ok(())
+}
@@ -1,12 +1,45 @@
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
 };
-use spl_token::instruction::{set_authority, AuthorityType};
-use crate::state::StakeDetails;
+use spl_token_2022::{
+    extension::ExtensionType,
+    instruction::{initialize_mint2, initialize_non_transferable_mint, initialize_permanent_delegate},
+    state::Mint as Token2022Mint,
+};
+use crate::{errors::NftStakingError, state::{BoostTier, Level, Milestone, RewardStream, SetBonusTier, StakeDetails, BASIS_POINTS, MAX_LEVELS, MAX_MILESTONES, MAX_REWARD_STREAMS, MAX_SET_BONUS_TIERS, MAX_BOOST_TIERS, STAKE_DETAILS_RESERVED_LEN}, utils::{is_writable_or_unused, load_protocol_config, RewardMintSetup}};
+
+#[derive(BorshDeserialize)]
+pub struct InitStakingArgs {
+    pub reward_rate: u64,   // fixed-point, see REWARD_RATE_SCALE
+    pub reward_period: i64, // in seconds
+    pub min_stake_duration: i64,
+    pub early_unstake_penalty_bps: u16,
+    pub early_unstake_flat_fee: u64,
+    pub unbonding_period: i64, // 0 disables the two-step unstake
+}
+
+pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo], args: InitStakingArgs) -> ProgramResult{
+
+    if args.reward_rate == 0 || args.reward_period <= 0 {
+        msg!("Invalid reward rate : {} per {} seconds", args.reward_rate, args.reward_period);
+        return Err(NftStakingError::InvalidRewardRate.into());
+    }
+
+    if args.early_unstake_penalty_bps as u64 > BASIS_POINTS {
+        msg!("Invalid early unstake penalty : {} bps", args.early_unstake_penalty_bps);
+        return Err(NftStakingError::InvalidBasisPoints.into());
+    }
 
+    if args.min_stake_duration < 0 {
+        msg!("Invalid minimum stake duration : {} seconds", args.min_stake_duration);
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
-pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo]) -> ProgramResult{
+    if args.unbonding_period < 0 {
+        msg!("Invalid unbonding period : {} seconds", args.unbonding_period);
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     let iter = &mut accounts.iter();
 
@@ -18,10 +51,10 @@ pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo]) -> ProgramResu
         return Err(ProgramError::InvalidAccountData);
     }
 
-    // isWritable
+    // isWritable, the system program for a points-only pool
     let reward_mint = next_account_info(iter)?;
 
-    if !reward_mint.is_writable {
+    if !is_writable_or_unused(reward_mint) {
         msg!("Reward Mint is not writable");
         return Err(ProgramError::InvalidAccountData);
     }
@@ -36,10 +69,48 @@ pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo]) -> ProgramResu
         return Err(ProgramError::InvalidAccountData);
     }
 
+    // isWritable, ata of the stake details account for the reward mint, unused by points-only pools
+    let treasury_token_account = next_account_info(iter)?;
+
+    if !is_writable_or_unused(treasury_token_account) {
+        msg!("Treasury Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // reward token account of the protocol treasury receiving the protocol fee
+    let fee_treasury_account = next_account_info(iter)?;
+
+    let protocol_config_account = next_account_info(iter)?;
+
+    // isWritable, receives the creation fee
+    let protocol_treasury = next_account_info(iter)?;
+
+    if !protocol_treasury.is_writable {
+        msg!("Protocol Treasury is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let token_program = next_account_info(iter)?;
 
+    let associated_token_program = next_account_info(iter)?;
+
     let system_program = next_account_info(iter)?;
 
+    // isWritable, pda of the soulbound staking badge mint
+    let badge_mint = next_account_info(iter)?;
+
+    if !badge_mint.is_writable {
+        msg!("Badge Mint is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_2022_program = next_account_info(iter)?;
+
+    if *token_2022_program.key != spl_token_2022::id() {
+        msg!("Token 2022 Program expected : {}, received : {}", spl_token_2022::id(), token_2022_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // create a pda 
     let (stake_details_key, stake_details_bump) = Pubkey::find_program_address(
         &[b"stake_details", 
@@ -54,11 +125,46 @@ pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo]) -> ProgramResu
         return Err(ProgramError::InvalidAccountData);
     }
 
+    let protocol_config = load_protocol_config(program_id, protocol_config_account)?;
+
+    let reward_mint_setup = RewardMintSetup {
+        reward_mint,
+        treasury_token_account,
+        fee_treasury_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    };
+
+    // points-only pools accrue points until a reward mint is attached
+    let points_only = *reward_mint.key == Pubkey::default();
+
+    let treasury_key = if points_only {
+        msg!("Points-only pool");
+        Pubkey::default()
+    } else {
+        reward_mint_setup.validate(&protocol_config, &stake_details_key)?
+    };
+
+    if protocol_config.treasury != *protocol_treasury.key {
+        msg!("Protocol Treasury expected : {}, received : {}", protocol_config.treasury, protocol_treasury.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     if stake_details_acc.data.borrow().len() != 0 {
         msg!("Stake Details Account is already initialized");
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
+    if protocol_config.creation_fee > 0 {
+        msg!("Charging the pool creation fee of {} lamports", protocol_config.creation_fee);
+
+        invoke(
+            &system_instruction::transfer(user.key, protocol_treasury.key, protocol_config.creation_fee),
+            &[user.clone(), protocol_treasury.clone(), system_program.clone()]
+        )?;
+    }
+
     let space = StakeDetails::LEN;
     let lamports = Rent::get()?.minimum_balance(space);
 
@@ -81,37 +187,100 @@ pub fn init_staking(program_id: &Pubkey,accounts: &[AccountInfo]) -> ProgramResu
 
     msg!("Successfully Created Stake Details Account");
 
+    let (badge_mint_key, badge_mint_bump) = Pubkey::find_program_address(
+        &[b"badge", stake_details_key.as_ref()],
+        program_id
+    );
+
+    if badge_mint_key != *badge_mint.key {
+        msg!("Badge Mint expected : {}, received : {}", badge_mint_key, badge_mint.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // badges can not be transferred, and only the pool can burn them through the permanent delegate
+    let badge_mint_space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::PermanentDelegate,
+    ])?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            user.key,
+            badge_mint.key,
+            Rent::get()?.minimum_balance(badge_mint_space),
+            badge_mint_space as u64,
+            token_2022_program.key
+        ),
+        &[user.clone(), badge_mint.clone(), system_program.clone()],
+        &[&[b"badge", stake_details_key.as_ref(), &[badge_mint_bump]]]
+    )?;
+
+    invoke(
+        &initialize_non_transferable_mint(token_2022_program.key, badge_mint.key)?,
+        &[badge_mint.clone(), token_2022_program.clone()]
+    )?;
+
+    invoke(
+        &initialize_permanent_delegate(token_2022_program.key, badge_mint.key, &stake_details_key)?,
+        &[badge_mint.clone(), token_2022_program.clone()]
+    )?;
+
+    invoke(
+        &initialize_mint2(token_2022_program.key, badge_mint.key, &stake_details_key, None, 0)?,
+        &[badge_mint.clone(), token_2022_program.clone()]
+    )?;
+
+    msg!("Successfully Created the Staking Badge Mint");
+
     let stake_details = StakeDetails{
         creator: *user.key,
         reward_token_mint: *reward_mint.key,
         collection_mint: *collection_mint.key,
-        bump_seed: stake_details_bump
+        bump_seed: stake_details_bump,
+        reward_rate: args.reward_rate,
+        reward_period: args.reward_period,
+        treasury: treasury_key,
+        min_stake_duration: args.min_stake_duration,
+        early_unstake_penalty_bps: args.early_unstake_penalty_bps,
+        early_unstake_flat_fee: args.early_unstake_flat_fee,
+        unbonding_period: args.unbonding_period,
+        fee_treasury: if points_only { Pubkey::default() } else { *fee_treasury_account.key },
+        protocol_fee_bps: protocol_config.default_fee_bps,
+        protocol_fee_cap: protocol_config.default_fee_cap,
+        reward_stream_count: 0,
+        reward_streams: [RewardStream::default(); MAX_REWARD_STREAMS],
+        badge_mint: badge_mint_key,
+        milestone_collection: Pubkey::default(),
+        milestone_count: 0,
+        milestones: [Milestone::default(); MAX_MILESTONES],
+        level_count: 0,
+        levels: [Level::default(); MAX_LEVELS],
+        set_bonus_tier_count: 0,
+        set_bonus_tiers: [SetBonusTier::default(); MAX_SET_BONUS_TIERS],
+        boost_tier_count: 0,
+        boost_tiers: [BoostTier::default(); MAX_BOOST_TIERS],
+        potion_price: 0,
+        potion_boost_bps: 0,
+        potion_duration: 0,
+        revenue_vault: Pubkey::default(),
+        revenue_per_share: 0,
+        total_staked: 0,
+        vesting_period: 0,
+        instant_withdraw_penalty_bps: 0,
+        referral_bps: 0,
+        crank_tip_bps: 0,
+        sponsors_rent: false,
+        reward_token_program: if points_only { Pubkey::default() } else { *token_program.key },
+        reserved: [0; STAKE_DETAILS_RESERVED_LEN],
     };
 
     stake_details.serialize(&mut *stake_details_acc.data.borrow_mut())?;
 
     msg!("Successfully written stake_details in onchain");
 
-    let mint_authority_ix = set_authority(
-        token_program.key, 
-        reward_mint.key, 
-        Some(stake_details_acc.key), 
-        AuthorityType::MintTokens,   // set as mint authority
-        user.key, 
-        &[user.key]
-    )?;
-
-    invoke( 
-        &mint_authority_ix,
-        &[
-            user.clone(), 
-            stake_details_acc.clone(), 
-            token_program.clone(), 
-            reward_mint.clone()
-        ]
-    )?;
-
-    msg!("Successfully transfered mint authority to stake details account");
+    if !points_only {
+        reward_mint_setup.attach(user, stake_details_acc)?;
+    }
 
     Ok(())
 
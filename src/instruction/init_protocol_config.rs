@@ -0,0 +1,133 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, bpf_loader_upgradeable::{self, UpgradeableLoaderState}, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::{ProtocolConfig, MAX_PROTOCOL_FEE_BPS, MAX_TOKEN_PROGRAMS}};
+
+#[derive(BorshDeserialize)]
+pub struct ProtocolConfigArgs {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub creation_fee: u64, // in lamports
+    pub default_fee_bps: u16,
+    pub default_fee_cap: u64, // 0 for no cap
+    pub allowed_token_programs: Vec<Pubkey>,
+}
+
+impl ProtocolConfigArgs {
+
+    pub fn to_config(&self, bump_seed: u8) -> Result<ProtocolConfig, ProgramError> {
+
+        if self.default_fee_bps > MAX_PROTOCOL_FEE_BPS {
+            msg!("Protocol fee of {} bps exceeds the maximum of {} bps", self.default_fee_bps, MAX_PROTOCOL_FEE_BPS);
+            return Err(NftStakingError::ProtocolFeeTooHigh.into());
+        }
+
+        if self.allowed_token_programs.is_empty() || self.allowed_token_programs.len() > MAX_TOKEN_PROGRAMS {
+            msg!("Between 1 and {} token programs can be allowed", MAX_TOKEN_PROGRAMS);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // the payouts use the token program passed for the staked NFT and its receipt, Token-2022 reward mints are not supported
+        if let Some(token_program) = self.allowed_token_programs.iter().find(|token_program| **token_program != spl_token::id()) {
+            msg!("Token Program {} can not be allowed, reward mints must be owned by {}", token_program, spl_token::id());
+            return Err(NftStakingError::TokenProgramNotAllowed.into());
+        }
+
+        let mut allowed_token_programs = [Pubkey::default(); MAX_TOKEN_PROGRAMS];
+        allowed_token_programs[..self.allowed_token_programs.len()].copy_from_slice(&self.allowed_token_programs);
+
+        Ok(ProtocolConfig {
+            admin: self.admin,
+            treasury: self.treasury,
+            creation_fee: self.creation_fee,
+            default_fee_bps: self.default_fee_bps,
+            default_fee_cap: self.default_fee_cap,
+            allowed_token_programs,
+            bump_seed,
+        })
+    }
+}
+
+/// Creates the singleton protocol config. Only the upgrade authority of the program can do it.
+pub fn init_protocol_config(program_id: &Pubkey, accounts: &[AccountInfo], args: ProtocolConfigArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    // upgrade authority of the program, pays for the config account
+    let authority = next_account_info(iter)?;
+
+    if !authority.is_signer || !authority.is_writable {
+        msg!("Authority account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let protocol_config_account = next_account_info(iter)?;
+
+    if !protocol_config_account.is_writable {
+        msg!("Protocol Config Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let program_data_account = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+
+    let (program_data_key, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    if program_data_key != *program_data_account.key {
+        msg!("Program Data Account expected : {}, received : {}", program_data_key, program_data_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let program_data = program_data_account.data.borrow();
+    let metadata = program_data
+        .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    let upgrade_authority = match bincode::deserialize(metadata) {
+        Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) => upgrade_authority_address,
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+
+    if upgrade_authority != Some(*authority.key) {
+        msg!("Only the upgrade authority of the program can create the protocol config");
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    let (protocol_config_key, protocol_config_bump) = Pubkey::find_program_address(&[b"protocol_config"], program_id);
+
+    if protocol_config_key != *protocol_config_account.key {
+        msg!("Protocol Config expected : {}, received : {}", protocol_config_key, protocol_config_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if protocol_config_account.data.borrow().len() != 0 {
+        msg!("Protocol Config is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let protocol_config = args.to_config(protocol_config_bump)?;
+
+    let space = ProtocolConfig::LEN;
+    let lamports = Rent::get()?.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            authority.key,
+            protocol_config_account.key,
+            lamports,
+            space as u64,
+            program_id
+        ),
+        &[authority.clone(), protocol_config_account.clone(), system_program.clone()],
+        &[&[b"protocol_config", &[protocol_config_bump]]]
+    )?;
+
+    protocol_config.serialize(&mut *protocol_config_account.data.borrow_mut())?;
+
+    msg!("Successfully created the protocol config, admin : {}", protocol_config.admin);
+
+    Ok(())
+}
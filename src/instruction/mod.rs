@@ -2,8 +2,70 @@ mod init;
 mod stake;
 mod claim_rewards;
 mod unstake;
+mod request_unstake;
+mod withdraw_nft;
+mod cancel_unstake;
+mod init_protocol_config;
+mod update_protocol_config;
+mod add_reward_stream;
+mod configure_milestones;
+mod claim_milestone;
+mod configure_levels;
+mod evolve_nft;
+mod configure_set_bonus;
+mod configure_boost;
+mod add_boost;
+mod remove_boost;
+mod configure_potion;
+mod buy_potion;
+mod attach_reward_mint;
+mod convert_points;
+mod deposit_revenue;
+mod configure_vesting;
+mod withdraw_vested;
+mod configure_referral;
+mod configure_guild;
+mod claim_guild_rewards;
+mod configure_crank_tip;
+mod set_claim_delegate;
+mod set_reward_destination;
+mod configure_rent_sponsorship;
+mod fund_rent_vault;
+mod withdraw_rent_vault;
+mod withdraw_treasury;
 
 pub use init::*;
 pub use stake::*;
 pub use claim_rewards::*;
-pub use unstake::*;
\ No newline at end of file
+pub use unstake::*;
+pub use request_unstake::*;
+pub use withdraw_nft::*;
+pub use cancel_unstake::*;
+pub use init_protocol_config::*;
+pub use update_protocol_config::*;
+pub use add_reward_stream::*;
+pub use configure_milestones::*;
+pub use claim_milestone::*;
+pub use configure_levels::*;
+pub use evolve_nft::*;
+pub use configure_set_bonus::*;
+pub use configure_boost::*;
+pub use add_boost::*;
+pub use remove_boost::*;
+pub use configure_potion::*;
+pub use buy_potion::*;
+pub use attach_reward_mint::*;
+pub use convert_points::*;
+pub use deposit_revenue::*;
+pub use configure_vesting::*;
+pub use withdraw_vested::*;
+pub use configure_referral::*;
+pub use configure_guild::*;
+pub use claim_guild_rewards::*;
+pub use configure_crank_tip::*;
+pub use set_claim_delegate::*;
+pub use set_reward_destination::*;
+pub use configure_rent_sponsorship::*;
+pub use fund_rent_vault::*;
+pub use withdraw_rent_vault::*;
+pub use withdraw_treasury::*;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::instruction::transfer;
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::load_stake_for_holder};
+
+use super::BoostArgs;
+
+/// Unlocks reward tokens from the boost vault of a position, lowering its reward multiplier from now on.
+pub fn remove_boost(program_id: &Pubkey, accounts: &[AccountInfo], args: BoostArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User Account is Not Signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let user_reward_token_account = next_account_info(iter)?; // destination of the unlocked tokens
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let boost_vault_account = next_account_info(iter)?;
+    if !boost_vault_account.is_writable {
+        msg!("Boost Vault Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    if args.amount == 0 || args.amount > stake_data.locked_boost {
+        msg!("{} reward tokens are locked, requested : {}", stake_data.locked_boost, args.amount);
+        return Err(NftStakingError::InsufficientBoost.into());
+    }
+
+    let boost_vault_key = get_associated_token_address_with_program_id(
+        stake_account.key,
+        &stake_data.reward_mint,
+        token_program.key
+    );
+
+    if boost_vault_key != *boost_vault_account.key {
+        msg!("Boost Vault expected : {}, received : {}", boost_vault_key, boost_vault_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // an unbonding stake accrues nothing, its staked_at is reset on cancel
+    if !stake_data.is_unbonding() {
+        stake_data.checkpoint_rewards(&stake_details_data, Clock::get()?.unix_timestamp)?;
+    }
+
+    invoke_signed(
+        &transfer(token_program.key, boost_vault_account.key, user_reward_token_account.key, stake_account.key, &[stake_account.key], args.amount)?,
+        &[boost_vault_account.clone(), user_reward_token_account.clone(), stake_account.clone(), token_program.clone()],
+        &[&stake_data.signer_seeds()]
+    )?;
+
+    stake_data.set_locked_boost(&stake_details_data, stake_data.locked_boost - args.amount);
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("{} reward tokens unlocked, {} stay locked", args.amount, stake_data.locked_boost);
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{check_reward_destination, is_writable_or_unused, load_stake_for_holder, load_staker, pay_referral, pay_revenue_share, pay_unstake_rewards, RewardMinter}};
+
+/// First step of the two-step unstake. Pays the accrued rewards, stops the accrual
+/// and starts the unbonding period, after which the NFT can be withdrawn.
+pub fn request_unstake(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User Account is Not Signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let user_reward_token_account = next_account_info(iter)?;
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let staker_account = next_account_info(iter)?; // set bonus aggregate of the staker of the position
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let reward_token_mint_account = next_account_info(iter)?;
+    if !is_writable_or_unused(reward_token_mint_account) {
+        msg!("Reward Token Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let treasury_token_account = next_account_info(iter)?;
+    if !is_writable_or_unused(treasury_token_account) {
+        msg!("Treasury Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let fee_treasury_account = next_account_info(iter)?;
+    if !is_writable_or_unused(fee_treasury_account) {
+        msg!("Fee Treasury Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let revenue_vault_account = next_account_info(iter)?; // lamports of the SOL revenue share of the pool
+
+    let system_program = next_account_info(iter)?; // creates the vesting escrow of the holder in a vesting pool
+
+    let mut stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    // the pool signs for the mints it controls, a payout must only mint its reward mint
+    if !stake_details_data.is_points_only() && stake_details_data.reward_token_mint != *reward_token_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_token_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
+
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    // settled before the rewards are paid, a buyer does not pay the guild of the seller
+    stake_data.leave_guild_if_sold(user_account.key);
+
+    if stake_details_data.unbonding_period == 0 {
+        msg!("The pool does not have an unbonding period, use Unstake");
+        return Err(NftStakingError::UnbondingDisabled.into());
+    }
+
+    if stake_data.is_unbonding() {
+        msg!("Unstake already requested, unbonding until {}", stake_data.unbonding_ends_at);
+        return Err(NftStakingError::StakeUnbonding.into());
+    }
+
+    // the rewards go to the current holder of the receipt, points, vesting escrows and guilds are checked when credited
+    check_reward_destination(user_reward_token_account, user_account.key, &stake_data, &stake_details_data)?;
+
+    let now = Clock::get()?.unix_timestamp;
+
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account: reward_token_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account,
+        rent_payer: None,
+    };
+
+    let mut staker = load_staker(program_id, staker_account, stake_details_account, &stake_data.staker)?;
+
+    // the bonus of the current count is accrued before the position leaves the set
+    staker.update_boost_index(now)?;
+    let settled = stake_data.settle_rewards(&stake_details_data, &staker, now)?;
+    let referral = settled.share(stake_details_data.referral_bps);
+
+    // a position sold before already left the set of its staker
+    let staked_count = if stake_data.left_set { staker.staked_count } else { staker.staked_count.saturating_sub(1) };
+    staker.set_staked_count(&stake_details_data, staked_count);
+    staker.serialize(&mut *staker_account.data.borrow_mut())?;
+
+    pay_unstake_rewards(
+        &minter,
+        &stake_data,
+        settled,
+        user_account,
+        user_reward_token_account,
+        treasury_token_account,
+        iter,
+    )?;
+
+    // the referral accounts follow the reward stream accounts
+    pay_referral(&minter, user_account, &staker, referral, iter)?;
+
+    // unbonding NFTs do not share the revenue deposited from now on
+    pay_revenue_share(user_account, revenue_vault_account, &stake_details_data, &mut stake_data)?;
+
+    stake_data.unbonding_ends_at = now + stake_details_data.unbonding_period;
+    stake_data.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    stake_details_data.total_staked = stake_details_data.total_staked.saturating_sub(1);
+    stake_details_data.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
+    msg!("Unstake requested, the NFT can be withdrawn after {}", stake_data.unbonding_ends_at);
+
+    Ok(())
+}
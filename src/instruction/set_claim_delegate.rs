@@ -0,0 +1,67 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{state::StakeDetails, utils::load_stake_for_holder};
+
+#[derive(BorshDeserialize)]
+pub struct SetClaimDelegateArgs {
+    pub delegate: Pubkey, // Pubkey::default() revokes the delegate
+    pub destination: Pubkey, // reward token account the delegate claims to, Pubkey::default() for the ata of the holder
+}
+
+/// Lets the holder of a position register a wallet which can claim its rewards without the holder's signature.
+/// The delegate can only claim, it can never unstake, and the delegation lapses when the receipt is transferred.
+pub fn set_claim_delegate(program_id: &Pubkey, accounts: &[AccountInfo], args: SetClaimDelegateArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    // holder of the stake receipt
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_account = next_account_info(iter)?;
+
+    if !stake_account.is_writable {
+        msg!("Stake account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    stake.claim_delegate = args.delegate;
+    stake.claim_destination = args.destination;
+    stake.delegated_by = *user_account.key;
+
+    stake.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("Claim delegate : {}, destination : {}", args.delegate, args.destination);
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{state::StakeDetails, utils::{check_reward_token_account, load_stake_for_holder}};
+
+/// Changes the reward token account the rewards of a position are claimed to, e.g. after the holder closed
+/// its ata or migrated wallets. It applies while the holder which set it holds the receipt.
+pub fn set_reward_destination(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    // holder of the stake receipt
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+
+    let stake_account = next_account_info(iter)?;
+
+    if !stake_account.is_writable {
+        msg!("Stake account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account not owned by the Program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let reward_destination_account = next_account_info(iter)?; // new reward token account of the position
+
+    let stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let mut stake = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    check_reward_token_account(reward_destination_account, &stake_details)?;
+
+    stake.reward_mint_ata = *reward_destination_account.key;
+    stake.reward_destination_holder = *user_account.key;
+
+    stake.serialize(&mut *stake_account.data.borrow_mut())?;
+
+    msg!("Reward destination : {}", reward_destination_account.key);
+
+    Ok(())
+}
@@ -1,21 +1,30 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use mpl_token_metadata::accounts::Metadata;
-use spl_token::state::Account;
-use spl_associated_token_account::instruction::create_associated_token_account;
-use solana_program::{account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar};
-use crate::{errors::NftStakingError, state::{Stake,StakeDetails}};
-
-
+use spl_token::{instruction::{initialize_mint2, mint_to, set_authority, AuthorityType}, state::{Account, Mint}};
+use spl_associated_token_account::instruction::{create_associated_token_account, create_associated_token_account_idempotent};
+use solana_program::{account_info::{next_account_info, AccountInfo}, clock::Clock, program_option::COption, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar};
+use crate::{errors::NftStakingError, state::{Stake,StakeDetails, Staker, MAX_REWARD_STREAMS, STAKE_RESERVED_LEN}, utils::{check_rent_vault, check_reward_token_account, create_reward_ata, create_staker, load_guild, load_staker, StakingBadge}};
+
+#[derive(BorshDeserialize, Default)]
+pub struct StakeArgs {
+    pub for_guild: bool, // the rewards of the position go to the guild passed after the token 2022 program
+    pub sponsored: bool, // the rent is paid by the payer passed after the guild instead of the owner
+}
+
+/// Stakes an NFT of the collection of the pool. The owner of the NFT is the authority of the position, it is
+/// used in the seeds of the `Stake` pda and signs the transfer of the NFT. It can sign through a CPI, so
+/// multisig vaults and program-owned wallets can stake, and it needs no lamports when a payer sponsors the rent.
 pub fn stake(
     program_id: &Pubkey,
-    accounts: &[AccountInfo]
+    accounts: &[AccountInfo],
+    args: StakeArgs,
 ) -> ProgramResult{
 
     let iter = &mut accounts.iter();
 
-    let user = next_account_info(iter)?;
-    if !user.is_signer || !user.is_writable {
-        msg!("User account is not signer or writable");
+    let owner = next_account_info(iter)?; // owner of the nft, may sign via CPI
+    if !owner.is_signer || (!owner.is_writable && !args.sponsored) {
+        msg!("Owner account is not signer or writable");
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -29,7 +38,7 @@ pub fn stake(
         return Err(ProgramError::InvalidAccountData);
     }
 
-    let user_reward_token_account = next_account_info(iter)?; // reward token account for the user
+    let user_reward_token_account = next_account_info(iter)?; // reward token account for the user, its ata is created if missing
 
     let stake_details_account = next_account_info(iter)?; 
 
@@ -38,6 +47,11 @@ pub fn stake(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let stake_account = next_account_info(iter)?; // pda
 
     if !stake_account.is_writable {
@@ -45,6 +59,13 @@ pub fn stake(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    let staker_account = next_account_info(iter)?; // pda, set bonus aggregate of the user in the pool
+
+    if !staker_account.is_writable {
+        msg!("Staker account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let stake_ata = next_account_info(iter)?; // ata of the stake account to store the user nft
     
     if !stake_ata.is_writable {
@@ -52,17 +73,71 @@ pub fn stake(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    let receipt_mint = next_account_info(iter)?; // pda, mint of the transferable receipt of the position
+
+    if !receipt_mint.is_writable {
+        msg!("Receipt Mint is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_receipt_account = next_account_info(iter)?; // ata of the user for the receipt mint
+
+    if !user_receipt_account.is_writable {
+        msg!("User Receipt Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let token_program = next_account_info(iter)?;
     let associated_token_program = next_account_info(iter)?;
     let system_program = next_account_info(iter)?;
 
+    let badge_mint = next_account_info(iter)?; // soulbound staking badge of the pool
+
+    if !badge_mint.is_writable {
+        msg!("Badge Mint is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_badge_account = next_account_info(iter)?; // Token-2022 ata of the user for the badge mint
+
+    if !user_badge_account.is_writable {
+        msg!("User Badge Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_2022_program = next_account_info(iter)?;
+
+    // after the accounts every client already passes, the system program in a points-only pool
+    let reward_mint_account = next_account_info(iter)?;
+
+    let guild_account = if args.for_guild { Some(next_account_info(iter)?) } else { None };
+
+    // pays the rent of every account created for the position, the owner only signs for its NFT
+    let payer = if args.sponsored { next_account_info(iter)? } else { owner };
+
+    if !payer.is_signer || !payer.is_writable {
+        msg!("Payer account is not signer or writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let onchain_data = nft_metadata_account.data.borrow_mut();
         
     let metadata = Metadata::safe_deserialize(&onchain_data)?;
 
     let nft_collection = metadata.collection.ok_or(NftStakingError::NoCollectionSet)?;
 
-    let stake_details = StakeDetails::try_from_slice(&*stake_details_account.data.borrow())?;
+    let mut stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    // the receipt of the position is checked against the token program of the pool later on
+    if *token_program.key != stake_details.token_program_id() {
+        msg!("Token Program expected : {}, received : {}", stake_details.token_program_id(), token_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // the rent vault of the pool follows the payer when the pool sponsors the rent of its positions
+    let rent_vault_account = if stake_details.sponsors_rent { Some(next_account_info(iter)?) } else { None };
+
+    let referrer_account = iter.next(); // optional, staker account of the wallet which referred the user on its first stake in the pool
 
     // validate the metadata account points to the nft mint
     if metadata.mint != *nft_mint.key {
@@ -82,6 +157,17 @@ pub fn stake(
         return Err(NftStakingError::NftNotVerified.into());
     }
 
+    // the rewards of the position are claimed to this account, points-only pools pass the staker account instead
+    if !stake_details.is_points_only() {
+        if stake_details.reward_token_mint != *reward_mint_account.key {
+            msg!("Reward Mint expected : {}, received : {}", stake_details.reward_token_mint, reward_mint_account.key);
+            return Err(NftStakingError::RewardMintMismatch.into());
+        }
+
+        create_reward_ata(payer, owner, user_reward_token_account, reward_mint_account, token_program, associated_token_program, system_program)?;
+        check_reward_token_account(user_reward_token_account, &stake_details)?;
+    }
+
     let user_token_account_data = Account::unpack(&user_token_account.data.borrow())?;
 
     if user_token_account_data.amount == 0 {
@@ -93,7 +179,7 @@ pub fn stake(
         b"stake", 
         stake_details_account.key.as_ref(), 
         nft_mint.key.as_ref(), 
-        user.key.as_ref()
+        owner.key.as_ref()
     ];
 
     let (stake_key, stake_key_bump) = Pubkey::find_program_address(&seeds, program_id);
@@ -106,11 +192,41 @@ pub fn stake(
 
     // initialize stake account
 
+    let rent = Rent::get()?;
     let space : usize = Stake::LEN ;
-    let lamports : u64 = Rent::get()?.minimum_balance(space);
+    let lamports : u64 = rent.minimum_balance(space);
+
+    // the rent vault pays for the stake account and its NFT account, which only the program closes and refunds to it
+    // on unstake. It is a system account, so it signs for the lamports it pays and always stays rent exempt itself.
+    // The payer pays the receipt account, which its owner may close once the receipt was transferred
+    let (rent_vault_account, rent_vault_bump) = match rent_vault_account {
+        Some(rent_vault_account) => {
+            let rent_vault_bump = check_rent_vault(program_id, rent_vault_account, stake_details_account)?;
+
+            let sponsored_lamports = lamports + rent.minimum_balance(Account::LEN);
+            let available_lamports = rent_vault_account.lamports().saturating_sub(rent.minimum_balance(0));
+
+            // a short vault does not hold the stakes back, the payer pays the rent until the vault is funded again
+            if available_lamports < sponsored_lamports {
+                msg!("Rent Vault has {} lamports available, {} are needed, the payer pays the rent", available_lamports, sponsored_lamports);
+                (None, 0)
+            } else {
+                (Some(rent_vault_account), rent_vault_bump)
+            }
+        }
+        None => (None, 0),
+    };
+
+    let position_payer = rent_vault_account.unwrap_or(payer);
+
+    let stake_key_bump = [stake_key_bump];
+    let rent_vault_bump = [rent_vault_bump];
+    let stake_seeds: &[&[u8]] = &[b"stake", stake_details_account.key.as_ref(), nft_mint.key.as_ref(), owner.key.as_ref(), &stake_key_bump];
+    let rent_vault_seeds: &[&[u8]] = &[b"rent_vault", stake_details_account.key.as_ref(), &rent_vault_bump];
+    let signer_seeds: &[&[&[u8]]] = if rent_vault_account.is_some() { &[stake_seeds, rent_vault_seeds] } else { &[stake_seeds] };
 
     let stake_ix = system_instruction::create_account(
-        user.key, 
+        position_payer.key, 
         stake_account.key, 
         lamports, 
         space as u64, 
@@ -119,14 +235,8 @@ pub fn stake(
 
     invoke_signed(
         &stake_ix, 
-        &[user.clone(), stake_account.clone(), system_program.clone()], 
-        &[&[
-            b"stake", 
-            stake_details_account.key.as_ref(), 
-            nft_mint.key.as_ref(), 
-            user.key.as_ref(),
-            &[stake_key_bump],
-        ]]
+        &[position_payer.clone(), stake_account.clone(), system_program.clone()], 
+        signer_seeds
     )?;
 
     msg!("Stake account Successfully initialized");
@@ -149,7 +259,7 @@ pub fn stake(
 
     // initialize stake ata
     let stake_ata_ix = create_associated_token_account(
-        user.key,
+        position_payer.key,
         stake_account.key,
         nft_mint.key,
         token_program.key
@@ -158,7 +268,7 @@ pub fn stake(
     invoke_signed(
         &stake_ata_ix,
         &[
-            user.clone(), 
+            position_payer.clone(), 
             stake_ata.clone(), 
             stake_account.clone(), 
             nft_mint.clone(), 
@@ -166,13 +276,7 @@ pub fn stake(
             token_program.clone(), 
             system_program.clone()
         ],
-        &[&[
-            b"stake", 
-            stake_details_account.key.as_ref(), 
-            nft_mint.key.as_ref(), 
-            user.key.as_ref(),
-            &[stake_key_bump],
-        ]]
+        signer_seeds
     )?;
 
 
@@ -184,33 +288,202 @@ pub fn stake(
         token_program.key, 
         user_token_account.key, 
         stake_ata.key, 
-        user.key, 
-        &[user.key], 
+        owner.key, 
+        &[owner.key], 
         1 
     )?;
 
     invoke(
         &transfer_ix, 
-        &[user.clone(), user_token_account.clone(), stake_ata.clone(), token_program.clone()]
+        &[owner.clone(), user_token_account.clone(), stake_ata.clone(), token_program.clone()]
     )?;
     
 
     msg!("Successfully Transferred nft from user to stake");
 
+    let (receipt_mint_key, receipt_mint_bump) = Pubkey::find_program_address(
+        &[b"receipt", stake_account.key.as_ref()],
+        program_id
+    );
+
+    if receipt_mint_key != *receipt_mint.key {
+        msg!("Receipt Mint expected : {}, received : {}", receipt_mint_key, receipt_mint.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the receipt mint outlives the stake account, so it is reused when the same NFT is staked again by the owner.
+    // A token mint can not be closed, the payer of its rent is not refunded, unlike the rent payer of the position
+    if receipt_mint.data.borrow().len() == 0 {
+        let mint_ix = system_instruction::create_account(
+            payer.key,
+            receipt_mint.key,
+            Rent::get()?.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            token_program.key
+        );
+
+        invoke_signed(
+            &mint_ix,
+            &[payer.clone(), receipt_mint.clone(), system_program.clone()],
+            &[&[b"receipt", stake_account.key.as_ref(), &[receipt_mint_bump]]]
+        )?;
+
+        // the stake account is the mint authority, the pool signs for the reward mint and must not sign for receipts
+        invoke(
+            &initialize_mint2(token_program.key, receipt_mint.key, stake_account.key, None, 0)?,
+            &[receipt_mint.clone(), token_program.clone()]
+        )?;
+
+        msg!("Receipt Mint has been created");
+    } else {
+        let receipt_mint_data = Mint::unpack(&receipt_mint.data.borrow())?;
+
+        if receipt_mint_data.supply != 0 {
+            msg!("Receipt Mint {} is still in circulation", receipt_mint.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // receipt mints created before are handed over from the pool to the stake account
+        if receipt_mint_data.mint_authority == COption::Some(*stake_details_account.key) {
+            invoke_signed(
+                &set_authority(token_program.key, receipt_mint.key, Some(stake_account.key), AuthorityType::MintTokens, stake_details_account.key, &[stake_details_account.key])?,
+                &[receipt_mint.clone(), stake_details_account.clone(), token_program.clone()],
+                &[&stake_details.signer_seeds()]
+            )?;
+
+            msg!("Receipt Mint authority moved to the stake account");
+        }
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(payer.key, owner.key, receipt_mint.key, token_program.key),
+        &[
+            payer.clone(),
+            user_receipt_account.clone(),
+            owner.clone(),
+            receipt_mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ]
+    )?;
+
+    let mint_receipt_ix = mint_to(
+        token_program.key,
+        receipt_mint.key,
+        user_receipt_account.key,
+        stake_account.key,
+        &[stake_account.key],
+        1
+    )?;
+
+    invoke_signed(
+        &mint_receipt_ix,
+        &[receipt_mint.clone(), user_receipt_account.clone(), stake_account.clone()],
+        &[stake_seeds]
+    )?;
+
+    msg!("Minted the stake receipt to the user");
+
+    let badge = StakingBadge {
+        token_2022_program,
+        badge_mint_account: badge_mint,
+        stake_details_account,
+        stake_details: &stake_details,
+    };
+
+    badge.mint_to(payer, owner, user_badge_account, system_program, associated_token_program)?;
+
+    let guild = match guild_account {
+        Some(guild_account) => {
+            let guild_data = load_guild(program_id, guild_account, stake_details_account, guild_account.key)?;
+            msg!("Staking for the guild {} of {}", guild_account.key, guild_data.authority);
+            *guild_account.key
+        }
+        None => Pubkey::default(),
+    };
+
     let staked_at = Clock::get()?.unix_timestamp;
 
+    let mut staker = if staker_account.data.borrow().len() == 0 {
+        let mut staker = create_staker(program_id, payer, owner, staker_account, stake_details_account, system_program, staked_at)?;
+
+        if let Some(referrer_account) = referrer_account {
+            // only wallets which staked in the pool can refer, so made up wallets can not collect referrals
+            if referrer_account.owner != program_id {
+                msg!("Referrer Staker Account is not owned by the program");
+                return Err(NftStakingError::InvalidReferral.into());
+            }
+
+            let referrer = Staker::try_from_slice(&referrer_account.data.borrow())?;
+            let referrer_staker_key = Pubkey::create_program_address(&referrer.signer_seeds(), program_id)?;
+
+            if referrer_staker_key != *referrer_account.key || referrer.stake_details_key != *stake_details_account.key {
+                msg!("Referrer Staker Account {} does not belong to the pool {}", referrer_account.key, stake_details_account.key);
+                return Err(NftStakingError::InvalidReferral.into());
+            }
+
+            if referrer.owner == *owner.key {
+                msg!("A user cannot refer itself");
+                return Err(NftStakingError::InvalidReferral.into());
+            }
+
+            staker.referrer = referrer.owner;
+            msg!("Referred by {}", staker.referrer);
+        }
+
+        staker
+    } else {
+        load_staker(program_id, staker_account, stake_details_account, owner.key)?
+    };
+
+    // the bonus of the previous count is accrued before the new NFT raises it
+    staker.update_boost_index(staked_at)?;
+    staker.set_staked_count(&stake_details, staker.staked_count.checked_add(1).ok_or(NftStakingError::RewardOverflow)?);
+    staker.serialize(&mut *staker_account.data.borrow_mut())?;
+
+    msg!("{} NFTs staked in the pool, set bonus : {} bps", staker.staked_count, staker.set_bonus_bps);
+
     let stake = Stake{
         nft_mint: *nft_mint.key,
         reward_mint: stake_details.reward_token_mint,
         reward_mint_ata: *user_reward_token_account.key,
         stake_details_key: *stake_details_account.key,
         staked_at,
+        reward_carry: 0,
+        initial_staked_at: staked_at,
+        unbonding_ends_at: 0,
+        stream_carries: [0; MAX_REWARD_STREAMS],
+        staker: *owner.key,
+        receipt_mint: *receipt_mint.key,
+        bump_seed: stake_key_bump[0],
+        milestones_claimed: 0,
+        level: stake_details.level_of(&metadata.uri), // an NFT evolved in an earlier stake keeps its level
+        boost_index_snapshot: staker.boost_index,
+        locked_boost: 0,
+        potion_boost_bps: 0,
+        potion_expires_at: 0,
+        revenue_index_snapshot: stake_details.revenue_per_share, // only deposits made from now on are shared with this NFT
+        guild,
+        claim_delegate: Pubkey::default(),
+        claim_destination: Pubkey::default(),
+        delegated_by: Pubkey::default(),
+        reward_destination_holder: *owner.key,
+        rent_payer: *position_payer.key,
+        left_set: false,
+        boost_bps: 0,
+        revenue_carry: 0,
+        rent_vault_sponsored: rent_vault_account.is_some(),
+        reserved: [0; STAKE_RESERVED_LEN],
     };
 
     stake.serialize(&mut *stake_account.data.borrow_mut())?;    
 
+    stake_details.total_staked = stake_details.total_staked.checked_add(1).ok_or(NftStakingError::RewardOverflow)?;
+    stake_details.serialize(&mut *stake_details_account.data.borrow_mut())?;
+
     msg!("Successfully written stake data to onchain");
     Ok(())
 
     
-}
\ No newline at end of file
+}
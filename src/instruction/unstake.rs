@@ -1,13 +1,20 @@
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
 };
-use spl_associated_token_account::get_associated_token_address_with_program_id;
-use spl_token::{instruction::{close_account, mint_to_checked, transfer}, state::{Account, Mint}};
 
-use crate::{errors::NftStakingError, state::{Stake, StakeDetails}};
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{burn_receipt, check_reward_destination, is_writable_or_unused, load_stake_for_holder, check_rent_payer_account, load_staker, pay_referral, pay_revenue_share, pay_unstake_rewards, release_staked_nft, unlock_boost, RewardMinter, StakingBadge}};
 
-pub fn unstake(program_id: &Pubkey, accounts:&[AccountInfo]) -> ProgramResult{
+#[derive(BorshDeserialize, Default)]
+pub struct UnstakeArgs {
+    pub sponsored: bool, // the rent of the accounts created for the holder is paid by the payer passed after the rent payer
+}
+
+/// Unstakes a position of a pool without an unbonding period, pays its rewards and returns the NFT. The rent payer
+/// of the position follows the system program, the user when it paid the rent, followed by the payer of a sponsored
+/// unstake, the reward stream accounts and the referral accounts. The user may sign through a CPI and pays no rent
+/// when the unstake is sponsored.
+pub fn unstake(program_id: &Pubkey, accounts:&[AccountInfo], args: UnstakeArgs) -> ProgramResult{
 
     let iter = &mut accounts.iter();
 
@@ -17,6 +24,18 @@ pub fn unstake(program_id: &Pubkey, accounts:&[AccountInfo]) -> ProgramResult{
         msg!("User Account is Not Signer or Writable");
         return Err(ProgramError::MissingRequiredSignature);
     }
+
+    let user_receipt_account = next_account_info(iter)?;
+    if !user_receipt_account.is_writable {
+        msg!("User Receipt Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let receipt_mint_account = next_account_info(iter)?;
+    if !receipt_mint_account.is_writable {
+        msg!("Receipt Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
     
     let user_nft_token_account = next_account_info(iter)?;
     if !user_nft_token_account.is_writable {
@@ -37,6 +56,11 @@ pub fn unstake(program_id: &Pubkey, accounts:&[AccountInfo]) -> ProgramResult{
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if !stake_details_account.is_writable {
+        msg!("Stake Details Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let stake_account = next_account_info(iter)?;
     if !stake_account.is_writable {
         msg!("Stake Account is Not Writable");
@@ -48,178 +72,183 @@ pub fn unstake(program_id: &Pubkey, accounts:&[AccountInfo]) -> ProgramResult{
         return Err(ProgramError::InvalidAccountData);
     }
 
+    let staker_account = next_account_info(iter)?; // set bonus aggregate of the staker of the position
+
     let stake_nft_token_account = next_account_info(iter)?;
     if !stake_nft_token_account.is_writable {
         msg!("Stake NFT Token Account is Not Writable");
         return Err(ProgramError::InvalidAccountData);
     }
 
+    let boost_vault_account = next_account_info(iter)?; // reward token ata of the stake account
+    if !boost_vault_account.is_writable {
+        msg!("Boost Vault Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the locked boost goes back to a token account of the user, the rewards may be vested or paid to a guild
+    let user_boost_token_account = next_account_info(iter)?;
+    if !user_boost_token_account.is_writable {
+        msg!("User Boost Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     let nft_mint_account = next_account_info(iter)?;
 
     let reward_token_mint_account = next_account_info(iter)?;
-    if !reward_token_mint_account.is_writable {
+    if !is_writable_or_unused(reward_token_mint_account) {
         msg!("Reward Token Mint Account is Not Writable");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    let token_program = next_account_info(iter)?;
-
-    if stake_account.owner != program_id {
-        msg!("Stake Account not owned by the program");
-        return Err(ProgramError::InvalidAccountOwner);
+    let treasury_token_account = next_account_info(iter)?;
+    if !is_writable_or_unused(treasury_token_account) {
+        msg!("Treasury Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
     }
 
-    let seeds = [
-        b"stake", 
-        stake_details_account.key.as_ref(), 
-        nft_mint_account.key.as_ref(), 
-        user_account.key.as_ref()
-    ];
-
-    let (stake_account_key, stake_bump) = Pubkey::find_program_address(&seeds, program_id);
-
-    if stake_account_key != *stake_account.key{
-        msg!("Invalid Stake Account, expected : {}", stake_account_key);
+    let fee_treasury_account = next_account_info(iter)?;
+    if !is_writable_or_unused(fee_treasury_account) {
+        msg!("Fee Treasury Account is Not Writable");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    if stake_account.data.borrow().len() == 0 {
-        msg!("Stake Account is Not Initialized");
-        return Err(NftStakingError::AccountNotInitialized.into());
-    }
+    let token_program = next_account_info(iter)?;
 
-    let stake_nft_token_key = get_associated_token_address_with_program_id(
-        stake_account.key, 
-        nft_mint_account.key, 
-        token_program.key
-    );
+    let badge_mint_account = next_account_info(iter)?;
+    if !badge_mint_account.is_writable {
+        msg!("Badge Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
 
-    if stake_nft_token_key != *stake_nft_token_account.key {
-        msg!("Invalid Stake NFT Token Account , expected : {}", stake_nft_token_key);
+    // the badge is burnt from the staker, who may not hold the receipt anymore
+    let staker_badge_account = next_account_info(iter)?;
+    if !staker_badge_account.is_writable {
+        msg!("Staker Badge Token Account is Not Writable");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    let stake_nft_token_account_data = Account::unpack(&stake_nft_token_account.data.borrow())?;
+    let token_2022_program = next_account_info(iter)?;
 
-    if stake_nft_token_account_data.amount == 0 {
-        msg!("No NFT Found in stake_nft_token_account : {}", stake_nft_token_account.key);
-        return Err(NftStakingError::NoNFTFound.into())
-    }
+    let revenue_vault_account = next_account_info(iter)?; // lamports of the SOL revenue share of the pool
 
-    let stake_data = Stake::try_from_slice(&stake_account.data.borrow())?;
-    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
-    
+    let system_program = next_account_info(iter)?; // creates the vesting escrow of the holder in a vesting pool
 
-    let transfer_ix = transfer(
-        token_program.key, 
-        stake_nft_token_account.key, 
-        user_nft_token_account.key, 
-        stake_account.key, 
-        &[stake_account.key], 
-        stake_nft_token_account_data.amount
-    )?;
+    let rent_payer_account = next_account_info(iter)?; // paid the rent of the position and gets it back
 
-    msg!("Transferring NFT back to the user");
-
-    let signers_seeds = [
-        b"stake",
-        stake_details_account.key.as_ref(),
-        stake_data.nft_mint.as_ref(),
-        user_account.key.as_ref(),
-        &[stake_bump]
-    ];
-
-    invoke_signed(
-        &transfer_ix, 
-        &[
-            stake_account.clone(),
-            stake_nft_token_account.clone(),
-            user_nft_token_account.clone(),
-            token_program.clone(),
-        ], 
-        &[&signers_seeds]
-    )?;
+    // pays the rent of the vesting escrows created for the holder and its referrer instead of the holder
+    let payer_account = if args.sponsored {
+        let payer_account = next_account_info(iter)?;
 
-    let now = Clock::get()?.unix_timestamp;
+        if !payer_account.is_signer || !payer_account.is_writable {
+            msg!("Payer account is not signer or writable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Some(payer_account)
+    } else {
+        None
+    };
 
-    let staked_duration = now - stake_data.staked_at;
-    let reward_amount = (staked_duration * 1000) as u64; // considering 1000 tokens per second
+    if stake_account.owner != program_id {
+        msg!("Stake Account not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
 
-    let reward_token_data = Mint::unpack(&reward_token_mint_account.data.borrow())?;
+    let mut stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
 
-    msg!("Sending {} reward tokens to the account : {}", reward_amount, user_reward_token_account.key);
+    // the pool signs for the mints it controls, a payout must only mint its reward mint
+    if !stake_details_data.is_points_only() && stake_details_data.reward_token_mint != *reward_token_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_token_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
 
-    let mint_ix = mint_to_checked(
-        token_program.key, 
-        &reward_token_mint_account.key, 
-        user_reward_token_account.key, 
-        stake_details_account.key, 
-        &[stake_details_account.key], 
-        reward_amount,
-        reward_token_data.decimals
+    let mut stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
     )?;
 
-    let stake_details_seeds = [
-        b"stake_details",
-        stake_details_data.creator.as_ref(),
-        stake_details_data.collection_mint.as_ref(),
-        &[stake_details_data.bump_seed]
-    ];
-
-    invoke_signed(
-        &mint_ix, 
-        &[
-            stake_details_account.clone(),
-            user_reward_token_account.clone(),
-            reward_token_mint_account.clone(),
-        ], 
-        &[&stake_details_seeds]
-    )?;
+    // settled before the rewards are paid, a buyer does not pay the guild of the seller
+    stake_data.leave_guild_if_sold(user_account.key);
 
-    msg!("Closing Stake NFT Account");
+    check_rent_payer_account(rent_payer_account, user_account.key, &stake_data)?;
 
-    let close_ix = close_account(
-        token_program.key, 
-        stake_nft_token_account.key, 
-        user_account.key, 
-        stake_account.key, 
-        &[stake_account.key]
-    )?;
+    if stake_details_data.unbonding_period > 0 {
+        msg!("The pool has an unbonding period of {} seconds, use RequestUnstake", stake_details_data.unbonding_period);
+        return Err(NftStakingError::UnbondingRequired.into());
+    }
+
+    // the rewards go to the current holder of the receipt, points, vesting escrows and guilds are checked when credited
+    check_reward_destination(user_reward_token_account, user_account.key, &stake_data, &stake_details_data)?;
+
+    let now = Clock::get()?.unix_timestamp;
 
-    invoke_signed(
-        &close_ix, 
-        &[
-            stake_account.clone(),
-            stake_nft_token_account.clone(),
-            user_account.clone(),
-            token_program.clone(),
-        ], 
-        &[&signers_seeds]
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account: reward_token_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account,
+        rent_payer: payer_account,
+    };
+
+    let mut staker = load_staker(program_id, staker_account, stake_details_account, &stake_data.staker)?;
+
+    // the bonus of the current count is accrued before the position leaves the set
+    staker.update_boost_index(now)?;
+    let settled = stake_data.settle_rewards(&stake_details_data, &staker, now)?;
+    let referral = settled.share(stake_details_data.referral_bps);
+
+    // a position sold before already left the set of its staker
+    let staked_count = if stake_data.left_set { staker.staked_count } else { staker.staked_count.saturating_sub(1) };
+    staker.set_staked_count(&stake_details_data, staked_count);
+    staker.serialize(&mut *staker_account.data.borrow_mut())?;
+
+    pay_unstake_rewards(
+        &minter,
+        &stake_data,
+        settled,
+        user_account,
+        user_reward_token_account,
+        treasury_token_account,
+        iter,
     )?;
-    
 
-    let stake_account_lamports = stake_account.lamports();
-    let user_account_lamports = user_account.lamports();
+    // the referral accounts follow the reward stream accounts
+    pay_referral(&minter, user_account, &staker, referral, iter)?;
 
-    let final_user_lamports = user_account_lamports.checked_add(stake_account_lamports);
+    pay_revenue_share(user_account, revenue_vault_account, &stake_details_data, &mut stake_data)?;
 
-    if let None = final_user_lamports {
-        msg!("Lamports Addition Overlfow Error");
-        return Err(NftStakingError::StakeAccountCloseError.into());
-    }
+    unlock_boost(user_account, user_boost_token_account, stake_account, boost_vault_account, token_program, &stake_data)?;
 
-    msg!("Transferring Lamports from Stake Account to User Account");
-    let mut user_lamports = user_account.lamports.borrow_mut();
-    **user_lamports = final_user_lamports.unwrap();
+    burn_receipt(user_account, user_receipt_account, rent_payer_account, receipt_mint_account, token_program, &stake_data)?;
 
-    msg!("Resetting Stake Account Lamports to 0");
-    let mut stake_account_lamports = stake_account.lamports.borrow_mut();
-    **stake_account_lamports = 0;
+    let badge = StakingBadge {
+        token_2022_program,
+        badge_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+    };
 
-    msg!("Closing Stake Account");
+    badge.burn_from(&stake_data.staker, staker_badge_account)?;
 
-    stake_account.assign(&solana_program::system_program::ID);
-    stake_account.realloc(0, false)?;
+    stake_details_data.total_staked = stake_details_data.total_staked.saturating_sub(1);
+    stake_details_data.serialize(&mut *stake_details_account.data.borrow_mut())?;
 
-    Ok(())
-}
\ No newline at end of file
+    release_staked_nft(
+        rent_payer_account,
+        user_nft_token_account,
+        stake_account,
+        stake_nft_token_account,
+        nft_mint_account,
+        token_program,
+        &stake_data.signer_seeds()
+    )
+}
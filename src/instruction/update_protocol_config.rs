@@ -0,0 +1,57 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{errors::NftStakingError, state::ProtocolConfig};
+
+use super::ProtocolConfigArgs;
+
+/// Replaces the protocol config, signed by the current admin. Existing pools keep their fees.
+pub fn update_protocol_config(program_id: &Pubkey, accounts: &[AccountInfo], args: ProtocolConfigArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let admin = next_account_info(iter)?;
+
+    if !admin.is_signer {
+        msg!("Admin account is not signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let protocol_config_account = next_account_info(iter)?;
+
+    if !protocol_config_account.is_writable {
+        msg!("Protocol Config Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if protocol_config_account.owner != program_id {
+        msg!("Protocol Config Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let protocol_config = ProtocolConfig::try_from_slice(&protocol_config_account.data.borrow())?;
+
+    let protocol_config_key = Pubkey::create_program_address(
+        &[b"protocol_config", &[protocol_config.bump_seed]],
+        program_id
+    )?;
+
+    if protocol_config_key != *protocol_config_account.key {
+        msg!("Protocol Config expected : {}, received : {}", protocol_config_key, protocol_config_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if protocol_config.admin != *admin.key {
+        msg!("Only the admin : {} can update the protocol config", protocol_config.admin);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    let protocol_config = args.to_config(protocol_config.bump_seed)?;
+    protocol_config.serialize(&mut *protocol_config_account.data.borrow_mut())?;
+
+    msg!("Successfully updated the protocol config");
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::{burn_receipt, load_stake_for_holder, check_rent_payer_account, release_staked_nft, unlock_boost, StakingBadge}};
+
+/// Second step of the two-step unstake. Returns the NFT and closes the stake accounts
+/// once the unbonding period is over. RequestUnstake already paid the rewards and reward streams in full.
+/// The last account is the rent payer of the position, the user when it paid the rent.
+pub fn withdraw_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer || !user_account.is_writable {
+        msg!("User Account is Not Signer or Writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let user_receipt_account = next_account_info(iter)?;
+    if !user_receipt_account.is_writable {
+        msg!("User Receipt Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let receipt_mint_account = next_account_info(iter)?;
+    if !receipt_mint_account.is_writable {
+        msg!("Receipt Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_nft_token_account = next_account_info(iter)?;
+    if !user_nft_token_account.is_writable {
+        msg!("User NFT Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_account = next_account_info(iter)?;
+    if !stake_account.is_writable {
+        msg!("Stake Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_account.owner != program_id {
+        msg!("Stake Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let stake_nft_token_account = next_account_info(iter)?;
+    if !stake_nft_token_account.is_writable {
+        msg!("Stake NFT Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_reward_token_account = next_account_info(iter)?; // receives the unlocked boost tokens
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let boost_vault_account = next_account_info(iter)?; // reward token ata of the stake account
+    if !boost_vault_account.is_writable {
+        msg!("Boost Vault Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let nft_mint_account = next_account_info(iter)?;
+
+    let token_program = next_account_info(iter)?;
+
+    let badge_mint_account = next_account_info(iter)?;
+    if !badge_mint_account.is_writable {
+        msg!("Badge Mint Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the badge is burnt from the staker, who may not hold the receipt anymore
+    let staker_badge_account = next_account_info(iter)?;
+    if !staker_badge_account.is_writable {
+        msg!("Staker Badge Token Account is Not Writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_2022_program = next_account_info(iter)?;
+
+    let rent_payer_account = next_account_info(iter)?; // paid the rent of the position and gets it back
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    let stake_data = load_stake_for_holder(
+        program_id,
+        user_account,
+        user_receipt_account,
+        stake_details_account,
+        &stake_details_data,
+        stake_account,
+        nft_mint_account,
+    )?;
+
+    check_rent_payer_account(rent_payer_account, user_account.key, &stake_data)?;
+
+    if !stake_data.is_unbonding() {
+        msg!("No unstake was requested for the stake account : {}", stake_account.key);
+        return Err(NftStakingError::UnstakeNotRequested.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if now < stake_data.unbonding_ends_at {
+        msg!("The NFT can be withdrawn after {}, now : {}", stake_data.unbonding_ends_at, now);
+        return Err(NftStakingError::UnbondingNotFinished.into());
+    }
+
+    unlock_boost(user_account, user_reward_token_account, stake_account, boost_vault_account, token_program, &stake_data)?;
+
+    burn_receipt(user_account, user_receipt_account, rent_payer_account, receipt_mint_account, token_program, &stake_data)?;
+
+    let badge = StakingBadge {
+        token_2022_program,
+        badge_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+    };
+
+    badge.burn_from(&stake_data.staker, staker_badge_account)?;
+
+    release_staked_nft(
+        rent_payer_account,
+        user_nft_token_account,
+        stake_account,
+        stake_nft_token_account,
+        nft_mint_account,
+        token_program,
+        &stake_data.signer_seeds()
+    )
+}
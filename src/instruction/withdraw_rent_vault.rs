@@ -0,0 +1,61 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
+};
+
+use crate::{errors::NftStakingError, state::StakeDetails, utils::check_rent_vault};
+
+use super::RentVaultArgs;
+
+/// Withdraws lamports from the rent vault of the pool to its creator. The vault either keeps its rent exemption
+/// or is emptied, the rent of open positions is refunded to it on unstake either way.
+pub fn withdraw_rent_vault(program_id: &Pubkey, accounts: &[AccountInfo], args: RentVaultArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer || !creator.is_writable {
+        msg!("Creator account is not signer or writable");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let rent_vault_account = next_account_info(iter)?;
+
+    let system_program = next_account_info(iter)?;
+
+    let stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can withdraw from the rent vault", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    let rent_vault_bump = check_rent_vault(program_id, rent_vault_account, stake_details_account)?;
+
+    let remaining_lamports = rent_vault_account.lamports()
+        .checked_sub(args.amount)
+        .ok_or(NftStakingError::InsufficientRentVault)?;
+
+    if remaining_lamports != 0 && remaining_lamports < Rent::get()?.minimum_balance(0) {
+        msg!("The rent vault must keep {} lamports or be emptied", Rent::get()?.minimum_balance(0));
+        return Err(NftStakingError::InsufficientRentVault.into());
+    }
+
+    invoke_signed(
+        &system_instruction::transfer(rent_vault_account.key, creator.key, args.amount),
+        &[rent_vault_account.clone(), creator.clone(), system_program.clone()],
+        &[&[b"rent_vault", stake_details_account.key.as_ref(), &[rent_vault_bump]]]
+    )?;
+
+    msg!("Withdrew {} lamports, the rent vault holds {}", args.amount, remaining_lamports);
+
+    Ok(())
+}
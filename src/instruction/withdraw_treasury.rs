@@ -0,0 +1,88 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey
+};
+use spl_token::instruction::transfer;
+
+use crate::{errors::NftStakingError, state::StakeDetails};
+
+#[derive(BorshDeserialize)]
+pub struct WithdrawTreasuryArgs {
+    pub amount: u64, // reward tokens moved out of the pool treasury
+}
+
+/// Withdraws reward tokens from the pool treasury, which collects the early unstake and instant withdrawal
+/// penalties, to a reward token account chosen by the creator of the pool.
+pub fn withdraw_treasury(program_id: &Pubkey, accounts: &[AccountInfo], args: WithdrawTreasuryArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let creator = next_account_info(iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let treasury_token_account = next_account_info(iter)?; // ata of the stake details account for the reward mint
+
+    let destination_token_account = next_account_info(iter)?;
+
+    if !treasury_token_account.is_writable || !destination_token_account.is_writable {
+        msg!("Treasury or Destination Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+
+    let stake_details = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details.creator != *creator.key {
+        msg!("Only the pool creator {} can withdraw from the treasury", stake_details.creator);
+        return Err(NftStakingError::Unauthorized.into());
+    }
+
+    if stake_details.is_points_only() {
+        msg!("Points-only pools have no treasury");
+        return Err(NftStakingError::PointsOnlyPool.into());
+    }
+
+    if stake_details.treasury != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account, expected : {}", stake_details.treasury);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if args.amount == 0 {
+        msg!("Withdraw amount must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &transfer(
+            token_program.key,
+            treasury_token_account.key,
+            destination_token_account.key,
+            stake_details_account.key,
+            &[stake_details_account.key],
+            args.amount
+        )?,
+        &[
+            treasury_token_account.clone(),
+            destination_token_account.clone(),
+            stake_details_account.clone(),
+            token_program.clone(),
+        ],
+        &[&stake_details.signer_seeds()]
+    )?;
+
+    msg!("Withdrew {} reward tokens from the treasury to {}", args.amount, destination_token_account.key);
+
+    Ok(())
+}
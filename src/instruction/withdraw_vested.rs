@@ -0,0 +1,129 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::{errors::NftStakingError, state::{StakeDetails, BASIS_POINTS}, utils::{load_vesting_escrow, RewardMinter}};
+
+#[derive(BorshDeserialize)]
+pub struct WithdrawVestedArgs {
+    pub instant: bool, // also withdraws the locked rewards, forfeiting instant_withdraw_penalty_bps of them
+}
+
+/// Mints the vested rewards of the escrow of the user. The protocol fee was taken when the rewards were vested.
+pub fn withdraw_vested(program_id: &Pubkey, accounts: &[AccountInfo], args: WithdrawVestedArgs) -> ProgramResult {
+
+    let iter = &mut accounts.iter();
+
+    let user_account = next_account_info(iter)?;
+
+    if !user_account.is_signer {
+        msg!("User account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vesting_escrow_account = next_account_info(iter)?;
+
+    let stake_details_account = next_account_info(iter)?;
+
+    if stake_details_account.owner != program_id {
+        msg!("Stake Details Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let user_reward_token_account = next_account_info(iter)?;
+
+    if !user_reward_token_account.is_writable {
+        msg!("User Reward Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_mint_account = next_account_info(iter)?;
+
+    if !reward_mint_account.is_writable {
+        msg!("Reward Mint Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let treasury_token_account = next_account_info(iter)?; // receives the penalty of an instant withdrawal
+
+    if !treasury_token_account.is_writable {
+        msg!("Treasury Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let token_program = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    let stake_details_data = StakeDetails::try_from_slice(&stake_details_account.data.borrow())?;
+
+    if stake_details_data.reward_token_mint != *reward_mint_account.key {
+        msg!("Reward Mint expected : {}, received : {}", stake_details_data.reward_token_mint, reward_mint_account.key);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
+
+    if stake_details_data.treasury != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account, expected : {}", stake_details_data.treasury);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_reward_ata = get_associated_token_address_with_program_id(
+        user_account.key,
+        reward_mint_account.key,
+        token_program.key
+    );
+
+    if user_reward_ata != *user_reward_token_account.key {
+        msg!("Reward Token Account expected : {}, received : {}", user_reward_ata, user_reward_token_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut vesting_escrow = load_vesting_escrow(program_id, vesting_escrow_account, stake_details_account, user_account.key)?;
+
+    vesting_escrow.update(Clock::get()?.unix_timestamp)?;
+
+    let mut withdrawn_amount = vesting_escrow.vested_amount;
+    let mut penalty_amount = 0;
+
+    if args.instant {
+        penalty_amount = (vesting_escrow.locked_amount as u128 * stake_details_data.instant_withdraw_penalty_bps as u128 / BASIS_POINTS as u128) as u64;
+        withdrawn_amount = withdrawn_amount
+            .checked_add(vesting_escrow.locked_amount - penalty_amount)
+            .ok_or(NftStakingError::RewardOverflow)?;
+
+        msg!("Withdrawing {} locked reward tokens early, penalty : {}", vesting_escrow.locked_amount, penalty_amount);
+
+        vesting_escrow.locked_amount = 0;
+    }
+
+    if withdrawn_amount == 0 && penalty_amount == 0 {
+        return Err(NftStakingError::NothingVested.into());
+    }
+
+    vesting_escrow.vested_amount = 0;
+    vesting_escrow.serialize(&mut *vesting_escrow_account.data.borrow_mut())?;
+
+    let minter = RewardMinter {
+        program_id,
+        token_program,
+        system_program,
+        reward_mint_account,
+        stake_details_account,
+        stake_details: &stake_details_data,
+        fee_treasury_account: treasury_token_account, // the fee was already taken
+        rent_payer: None,
+    };
+
+    if withdrawn_amount > 0 {
+        minter.mint_to(user_reward_token_account, withdrawn_amount)?;
+    }
+
+    if penalty_amount > 0 {
+        minter.mint_to(treasury_token_account, penalty_amount)?;
+    }
+
+    msg!("Withdrew {} vested reward tokens, {} still locked", withdrawn_amount, vesting_escrow.locked_amount);
+
+    Ok(())
+}
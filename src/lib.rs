@@ -2,6 +2,7 @@ mod processor;
 mod instruction;
 mod state;
 mod errors;
+mod utils;
 
 use processor::process_instruction;
 use solana_program::entrypoint;
@@ -6,32 +6,181 @@ use solana_program::{
     pubkey::Pubkey
 };
 
-use crate::instruction;
+use borsh::BorshDeserialize;
+
+use crate::instruction::{self, AddRewardStreamArgs, ClaimMilestoneArgs, ConfigureLevelsArgs, ConfigureMilestonesArgs, ConfigureSetBonusArgs, ConfigureBoostArgs, BoostArgs, ConfigurePotionArgs, ConvertPointsArgs, DepositRevenueArgs, ConfigureVestingArgs, WithdrawVestedArgs, ConfigureReferralArgs, ConfigureGuildArgs, ConfigureCrankTipArgs, SetClaimDelegateArgs, ClaimRewardsArgs, UnstakeArgs, ConfigureRentSponsorshipArgs, RentVaultArgs, WithdrawTreasuryArgs, InitStakingArgs, StakeArgs, ProtocolConfigArgs};
 
 pub enum StakingInstruction {
-    Initialize, 
-    Stake,
-    ClaimRewards,
-    UnStake
+    Initialize(InitStakingArgs), 
+    Stake(StakeArgs),
+    ClaimRewards(ClaimRewardsArgs),
+    UnStake(UnstakeArgs),
+    RequestUnstake,
+    WithdrawNft,
+    CancelUnstake,
+    InitProtocolConfig(ProtocolConfigArgs),
+    UpdateProtocolConfig(ProtocolConfigArgs),
+    AddRewardStream(AddRewardStreamArgs),
+    ConfigureMilestones(ConfigureMilestonesArgs),
+    ClaimMilestone(ClaimMilestoneArgs),
+    ConfigureLevels(ConfigureLevelsArgs),
+    EvolveNft,
+    ConfigureSetBonus(ConfigureSetBonusArgs),
+    ConfigureBoost(ConfigureBoostArgs),
+    AddBoost(BoostArgs),
+    RemoveBoost(BoostArgs),
+    ConfigurePotion(ConfigurePotionArgs),
+    BuyPotion,
+    AttachRewardMint,
+    ConvertPoints(ConvertPointsArgs),
+    DepositRevenue(DepositRevenueArgs),
+    ConfigureVesting(ConfigureVestingArgs),
+    WithdrawVested(WithdrawVestedArgs),
+    ConfigureReferral(ConfigureReferralArgs),
+    ConfigureGuild(ConfigureGuildArgs),
+    ClaimGuildRewards,
+    ConfigureCrankTip(ConfigureCrankTipArgs),
+    CrankClaimRewards,
+    SetClaimDelegate(SetClaimDelegateArgs),
+    DelegatedClaimRewards,
+    SetRewardDestination,
+    ConfigureRentSponsorship(ConfigureRentSponsorshipArgs),
+    FundRentVault(RentVaultArgs),
+    WithdrawRentVault(RentVaultArgs),
+    WithdrawTreasury(WithdrawTreasuryArgs),
 }
 
 impl StakingInstruction {
     pub fn unpack(instruction_data: &[u8]) -> Result<Self, ProgramError>{
 
-        let (variant, _rest) = instruction_data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        let (variant, rest) = instruction_data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
 
         match *variant {
             0 => {
-                Ok(Self::Initialize)
+                Ok(Self::Initialize(InitStakingArgs::try_from_slice(rest)?))
             },
             1 => {
-                Ok(Self::Stake)
+                // the args are optional, a bare variant stakes for the user
+                if rest.is_empty() {
+                    return Ok(Self::Stake(StakeArgs::default()));
+                }
+
+                Ok(Self::Stake(StakeArgs::try_from_slice(rest)?))
             },
             2 => {
-                Ok(Self::ClaimRewards)
+                // the args are optional, a bare variant is paid for by the holder
+                if rest.is_empty() {
+                    return Ok(Self::ClaimRewards(ClaimRewardsArgs::default()));
+                }
+
+                Ok(Self::ClaimRewards(ClaimRewardsArgs::try_from_slice(rest)?))
             },
             3 => {
-                Ok(Self::UnStake)
+                // the args are optional, a bare variant is paid for by the holder
+                if rest.is_empty() {
+                    return Ok(Self::UnStake(UnstakeArgs::default()));
+                }
+
+                Ok(Self::UnStake(UnstakeArgs::try_from_slice(rest)?))
+            },
+            4 => {
+                Ok(Self::RequestUnstake)
+            },
+            5 => {
+                Ok(Self::WithdrawNft)
+            },
+            6 => {
+                Ok(Self::CancelUnstake)
+            },
+            7 => {
+                Ok(Self::InitProtocolConfig(ProtocolConfigArgs::try_from_slice(rest)?))
+            },
+            8 => {
+                Ok(Self::UpdateProtocolConfig(ProtocolConfigArgs::try_from_slice(rest)?))
+            },
+            9 => {
+                Ok(Self::AddRewardStream(AddRewardStreamArgs::try_from_slice(rest)?))
+            },
+            10 => {
+                Ok(Self::ConfigureMilestones(ConfigureMilestonesArgs::try_from_slice(rest)?))
+            },
+            11 => {
+                Ok(Self::ClaimMilestone(ClaimMilestoneArgs::try_from_slice(rest)?))
+            },
+            12 => {
+                Ok(Self::ConfigureLevels(ConfigureLevelsArgs::try_from_slice(rest)?))
+            },
+            13 => {
+                Ok(Self::EvolveNft)
+            },
+            14 => {
+                Ok(Self::ConfigureSetBonus(ConfigureSetBonusArgs::try_from_slice(rest)?))
+            },
+            15 => {
+                Ok(Self::ConfigureBoost(ConfigureBoostArgs::try_from_slice(rest)?))
+            },
+            16 => {
+                Ok(Self::AddBoost(BoostArgs::try_from_slice(rest)?))
+            },
+            17 => {
+                Ok(Self::RemoveBoost(BoostArgs::try_from_slice(rest)?))
+            },
+            18 => {
+                Ok(Self::ConfigurePotion(ConfigurePotionArgs::try_from_slice(rest)?))
+            },
+            19 => {
+                Ok(Self::BuyPotion)
+            },
+            20 => {
+                Ok(Self::AttachRewardMint)
+            },
+            21 => {
+                Ok(Self::ConvertPoints(ConvertPointsArgs::try_from_slice(rest)?))
+            },
+            22 => {
+                Ok(Self::DepositRevenue(DepositRevenueArgs::try_from_slice(rest)?))
+            },
+            23 => {
+                Ok(Self::ConfigureVesting(ConfigureVestingArgs::try_from_slice(rest)?))
+            },
+            24 => {
+                Ok(Self::WithdrawVested(WithdrawVestedArgs::try_from_slice(rest)?))
+            },
+            25 => {
+                Ok(Self::ConfigureReferral(ConfigureReferralArgs::try_from_slice(rest)?))
+            },
+            26 => {
+                Ok(Self::ConfigureGuild(ConfigureGuildArgs::try_from_slice(rest)?))
+            },
+            27 => {
+                Ok(Self::ClaimGuildRewards)
+            },
+            28 => {
+                Ok(Self::ConfigureCrankTip(ConfigureCrankTipArgs::try_from_slice(rest)?))
+            },
+            29 => {
+                Ok(Self::CrankClaimRewards)
+            },
+            30 => {
+                Ok(Self::SetClaimDelegate(SetClaimDelegateArgs::try_from_slice(rest)?))
+            },
+            31 => {
+                Ok(Self::DelegatedClaimRewards)
+            },
+            32 => {
+                Ok(Self::SetRewardDestination)
+            },
+            33 => {
+                Ok(Self::ConfigureRentSponsorship(ConfigureRentSponsorshipArgs::try_from_slice(rest)?))
+            },
+            34 => {
+                Ok(Self::FundRentVault(RentVaultArgs::try_from_slice(rest)?))
+            },
+            35 => {
+                Ok(Self::WithdrawRentVault(RentVaultArgs::try_from_slice(rest)?))
+            },
+            36 => {
+                Ok(Self::WithdrawTreasury(WithdrawTreasuryArgs::try_from_slice(rest)?))
             },
             _ => {
                 msg!("No Instruction is set for the variant : {}", variant);
@@ -49,20 +198,152 @@ pub fn process_instruction(
 
     match StakingInstruction::unpack(instruction_data)? {
 
-        StakingInstruction::Initialize => {
-            instruction::init_staking(program_id, accounts_info)?
+        StakingInstruction::Initialize(args) => {
+            instruction::init_staking(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::Stake(args) =>{ 
+            instruction::stake(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ClaimRewards(args) => {
+            instruction::claim_rewards(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::UnStake(args) => {
+            instruction::unstake(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::RequestUnstake => {
+            instruction::request_unstake(program_id, accounts_info)?
+        }
+
+        StakingInstruction::WithdrawNft => {
+            instruction::withdraw_nft(program_id, accounts_info)?
+        }
+
+        StakingInstruction::CancelUnstake => {
+            instruction::cancel_unstake(program_id, accounts_info)?
+        }
+
+        StakingInstruction::InitProtocolConfig(args) => {
+            instruction::init_protocol_config(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::UpdateProtocolConfig(args) => {
+            instruction::update_protocol_config(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::AddRewardStream(args) => {
+            instruction::add_reward_stream(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureMilestones(args) => {
+            instruction::configure_milestones(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ClaimMilestone(args) => {
+            instruction::claim_milestone(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureLevels(args) => {
+            instruction::configure_levels(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::EvolveNft => {
+            instruction::evolve_nft(program_id, accounts_info)?
+        }
+
+        StakingInstruction::ConfigureSetBonus(args) => {
+            instruction::configure_set_bonus(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureBoost(args) => {
+            instruction::configure_boost(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::AddBoost(args) => {
+            instruction::add_boost(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::RemoveBoost(args) => {
+            instruction::remove_boost(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigurePotion(args) => {
+            instruction::configure_potion(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::BuyPotion => {
+            instruction::buy_potion(program_id, accounts_info)?
+        }
+
+        StakingInstruction::AttachRewardMint => {
+            instruction::attach_reward_mint(program_id, accounts_info)?
+        }
+
+        StakingInstruction::ConvertPoints(args) => {
+            instruction::convert_points(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::DepositRevenue(args) => {
+            instruction::deposit_revenue(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureVesting(args) => {
+            instruction::configure_vesting(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::WithdrawVested(args) => {
+            instruction::withdraw_vested(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureReferral(args) => {
+            instruction::configure_referral(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ConfigureGuild(args) => {
+            instruction::configure_guild(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::ClaimGuildRewards => {
+            instruction::claim_guild_rewards(program_id, accounts_info)?
+        }
+
+        StakingInstruction::ConfigureCrankTip(args) => {
+            instruction::configure_crank_tip(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::CrankClaimRewards => {
+            instruction::crank_claim_rewards(program_id, accounts_info)?
+        }
+
+        StakingInstruction::SetClaimDelegate(args) => {
+            instruction::set_claim_delegate(program_id, accounts_info, args)?
+        }
+
+        StakingInstruction::DelegatedClaimRewards => {
+            instruction::delegated_claim_rewards(program_id, accounts_info)?
+        }
+
+        StakingInstruction::SetRewardDestination => {
+            instruction::set_reward_destination(program_id, accounts_info)?
+        }
+
+        StakingInstruction::ConfigureRentSponsorship(args) => {
+            instruction::configure_rent_sponsorship(program_id, accounts_info, args)?
         }
 
-        StakingInstruction::Stake =>{ 
-            instruction::stake(program_id, accounts_info)?
+        StakingInstruction::FundRentVault(args) => {
+            instruction::fund_rent_vault(program_id, accounts_info, args)?
         }
 
-        StakingInstruction::ClaimRewards => {
-            instruction::claim_rewards(program_id, accounts_info)?
+        StakingInstruction::WithdrawRentVault(args) => {
+            instruction::withdraw_rent_vault(program_id, accounts_info, args)?
         }
 
-        StakingInstruction::UnStake => {
-            instruction::unstake(program_id, accounts_info)?
+        StakingInstruction::WithdrawTreasury(args) => {
+            instruction::withdraw_treasury(program_id, accounts_info, args)?
         }
     }
 
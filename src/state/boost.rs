@@ -0,0 +1,15 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub const MAX_BOOST_TIERS: usize = 4;
+
+/// Extra rewards, in bps of the pool reward rate, for a position locking at least
+/// `min_locked` reward tokens in its boost vault.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, Default)]
+pub struct BoostTier {
+    pub min_locked: u64,
+    pub boost_bps: u16,
+}
+
+impl BoostTier {
+    pub const LEN: usize = 8 + 2;
+}
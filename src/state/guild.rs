@@ -0,0 +1,53 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const MAX_GUILD_MEMBERS: usize = 8;
+
+/// A wallet sharing the rewards of a guild, in proportion to its weight.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, Default)]
+pub struct GuildMember {
+    pub wallet: Pubkey,
+    pub weight: u16,
+}
+
+impl GuildMember {
+    pub const LEN: usize = 32 + 2;
+}
+
+/// Positions staked for a guild, at ["guild", stake_details, authority], pay their pool reward token rewards
+/// to the guild, which splits them among its members by weight. Reward streams are still paid to the holder.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Guild {
+    pub stake_details_key: Pubkey,
+    pub authority: Pubkey, // manages the members
+    pub member_count: u8,
+    pub members: [GuildMember; MAX_GUILD_MEMBERS], // only the first member_count are in use
+    pub reward_balance: u64, // rewards of the guild positions not distributed yet
+    pub bump_seed: u8,
+    pub reserved: [u8; GUILD_RESERVED_LEN], // zeroed room for new fields, so the size of existing guilds never changes
+}
+
+// new fields take their bytes from `reserved` and must treat zero as their default
+pub const GUILD_RESERVED_LEN: usize = 64;
+
+impl Guild {
+    pub const LEN: usize = 32 + 32 + 1 + GuildMember::LEN * MAX_GUILD_MEMBERS + 8 + 1 + GUILD_RESERVED_LEN;
+
+    pub fn active_members(&self) -> &[GuildMember] {
+        &self.members[..self.member_count as usize]
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.active_members().iter().map(|member| member.weight as u64).sum()
+    }
+
+    /// Share of the reward balance distributed to `member`, rounded down.
+    pub fn member_amount(&self, member: &GuildMember) -> u64 {
+        (self.reward_balance as u128 * member.weight as u128 / self.total_weight() as u128) as u64
+    }
+
+    /// Whether a distribution would pay something, the rounding remainder alone is not distributed.
+    pub fn has_undistributed_rewards(&self) -> bool {
+        self.active_members().iter().any(|member| self.member_amount(member) > 0)
+    }
+}
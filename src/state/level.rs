@@ -0,0 +1,44 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::MAX_URI_LENGTH;
+
+use super::milestone::unpad;
+
+pub const MAX_LEVELS: usize = 3;
+
+/// Level a staked NFT evolves to once it has been staked for `duration` seconds without interruption.
+/// The traits of the level live in the off-chain json at `uri`, which is zero-padded.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy)]
+pub struct Level {
+    pub duration: i64,
+    pub uri: [u8; MAX_URI_LENGTH],
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            duration: 0,
+            uri: [0; MAX_URI_LENGTH],
+        }
+    }
+}
+
+impl Level {
+    pub const LEN: usize = 8 + MAX_URI_LENGTH;
+
+    /// Returns None when the uri is too long.
+    pub fn new(duration: i64, uri: &str) -> Option<Self> {
+
+        if uri.len() > MAX_URI_LENGTH {
+            return None;
+        }
+
+        let mut level = Self { duration, ..Default::default() };
+        level.uri[..uri.len()].copy_from_slice(uri.as_bytes());
+
+        Some(level)
+    }
+
+    pub fn uri(&self) -> String {
+        unpad(&self.uri)
+    }
+}
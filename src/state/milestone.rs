@@ -0,0 +1,55 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+
+pub const MAX_MILESTONES: usize = 3;
+
+/// Badge NFT a position can claim once it has been staked for `duration` seconds without interruption.
+/// The name and uri are zero-padded, as the account has a fixed size.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy)]
+pub struct Milestone {
+    pub duration: i64,
+    pub name: [u8; MAX_NAME_LENGTH],
+    pub uri: [u8; MAX_URI_LENGTH],
+}
+
+impl Default for Milestone {
+    fn default() -> Self {
+        Self {
+            duration: 0,
+            name: [0; MAX_NAME_LENGTH],
+            uri: [0; MAX_URI_LENGTH],
+        }
+    }
+}
+
+impl Milestone {
+    pub const LEN: usize = 8 + MAX_NAME_LENGTH + MAX_URI_LENGTH;
+
+    /// Returns None when the name or the uri is too long.
+    pub fn new(duration: i64, name: &str, uri: &str) -> Option<Self> {
+
+        if name.len() > MAX_NAME_LENGTH || uri.len() > MAX_URI_LENGTH {
+            return None;
+        }
+
+        let mut milestone = Self { duration, ..Default::default() };
+
+        milestone.name[..name.len()].copy_from_slice(name.as_bytes());
+        milestone.uri[..uri.len()].copy_from_slice(uri.as_bytes());
+
+        Some(milestone)
+    }
+
+    pub fn name(&self) -> String {
+        unpad(&self.name)
+    }
+
+    pub fn uri(&self) -> String {
+        unpad(&self.uri)
+    }
+}
+
+pub(super) fn unpad(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
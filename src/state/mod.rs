@@ -1,5 +1,23 @@
 mod stake_details;
 mod stake;
+mod protocol_config;
+mod reward_stream;
+mod milestone;
+mod level;
+mod staker;
+mod set_bonus;
+mod boost;
+mod vesting_escrow;
+mod guild;
 
 pub use stake_details::*;
-pub use stake::*;
\ No newline at end of file
+pub use stake::*;
+pub use protocol_config::*;
+pub use reward_stream::*;
+pub use milestone::*;
+pub use level::*;
+pub use staker::*;
+pub use set_bonus::*;
+pub use boost::*;
+pub use vesting_escrow::*;
+pub use guild::*;
\ No newline at end of file
@@ -0,0 +1,25 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const MAX_TOKEN_PROGRAMS: usize = 2;
+
+/// Singleton account holding the protocol level settings every pool is created with.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,      // receives the creation fee and owns the protocol fee token accounts
+    pub creation_fee: u64,     // lamports charged by init_staking
+    pub default_fee_bps: u16,  // protocol fee of new pools
+    pub default_fee_cap: u64,  // protocol fee cap of new pools, 0 for no cap
+    pub allowed_token_programs: [Pubkey; MAX_TOKEN_PROGRAMS], // unused slots are Pubkey::default(), only the SPL Token program for now
+    pub bump_seed: u8,
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 32 + 32 + 8 + 2 + 8 + 32 * MAX_TOKEN_PROGRAMS + 1;
+
+    // configs created before Token-2022 was refused may still list it
+    pub fn is_token_program_allowed(&self, token_program: &Pubkey) -> bool {
+        *token_program == spl_token::id() && self.allowed_token_programs.contains(token_program)
+    }
+}
@@ -0,0 +1,28 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+// extra reward streams a pool can have on top of its own reward token
+pub const MAX_REWARD_STREAMS: usize = 3;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum FundingMode {
+    #[default]
+    Mint,  // the stake details pda is the mint authority
+    Vault, // paid out of a token account owned by the stake details pda
+}
+
+/// Additional reward token paid to the stakers of a pool, e.g. by a partner project.
+/// The protocol fee and the early unstake penalty only apply to the pool reward token.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, Default)]
+pub struct RewardStream {
+    pub mint: Pubkey,
+    pub funding_mode: FundingMode,
+    pub vault: Pubkey,      // Pubkey::default() in Mint mode
+    pub reward_rate: u64,   // fixed-point, see REWARD_RATE_SCALE
+    pub reward_period: i64, // in seconds
+    pub start_time: i64,    // stakes only accrue this stream from here on
+}
+
+impl RewardStream {
+    pub const LEN: usize = 32 + 1 + 32 + 8 + 8 + 8;
+}
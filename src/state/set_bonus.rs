@@ -0,0 +1,15 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub const MAX_SET_BONUS_TIERS: usize = 4;
+
+/// Extra rewards, in bps of the pool reward rate, for every position of a wallet
+/// staking at least `min_staked` NFTs of the pool.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, Default)]
+pub struct SetBonusTier {
+    pub min_staked: u8,
+    pub bonus_bps: u16,
+}
+
+impl SetBonusTier {
+    pub const LEN: usize = 1 + 2;
+}
@@ -1,15 +1,236 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::errors::NftStakingError;
+
+use super::{RewardStream, StakeDetails, Staker, BASIS_POINTS, MAX_REWARD_STREAMS, REWARD_RATE_SCALE};
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Stake {
     pub stake_details_key : Pubkey,
     pub nft_mint : Pubkey,
     pub reward_mint: Pubkey,
-    pub reward_mint_ata: Pubkey,
+    pub reward_mint_ata: Pubkey, // reward token account the rewards are claimed to, chosen by reward_destination_holder
     pub staked_at: i64,
+    pub reward_carry: u128, // undistributed remainder, in units of 1 / (reward_period * REWARD_RATE_SCALE) tokens
+    pub initial_staked_at: i64, // unlike staked_at, this is not reset on claims
+    pub unbonding_ends_at: i64, // 0 unless an unstake was requested
+    pub stream_carries: [u128; MAX_REWARD_STREAMS], // reward_carry of each reward stream of the pool
+    pub staker: Pubkey, // the wallet which staked the NFT, part of the pda seeds
+    pub receipt_mint: Pubkey, // whoever holds the receipt token owns the position
+    pub bump_seed: u8,
+    pub milestones_claimed: u8, // bit i is set once milestone i of the pool was claimed
+    pub level: u8, // 0 until the NFT evolves, else the index of its pool level + 1
+    pub boost_index_snapshot: u128, // boost_index of the staker when the rewards were last settled
+    pub locked_boost: u64, // reward tokens locked in the boost vault, the ata of the stake account
+    pub potion_boost_bps: u16, // boost of the last potion, applied until potion_expires_at
+    pub potion_expires_at: i64,
+    pub revenue_index_snapshot: u128, // revenue_per_share of the pool when the revenue share was last paid
+    pub guild: Pubkey, // guild the rewards are paid to, Pubkey::default() if the position was not staked for a guild
+    pub claim_delegate: Pubkey, // wallet which may claim the rewards for the holder, Pubkey::default() if none
+    pub claim_destination: Pubkey, // reward token account the delegate claims to, Pubkey::default() for the ata of the holder
+    pub delegated_by: Pubkey, // holder which set the delegate, the delegation lapses once the receipt changes hands
+    pub reward_destination_holder: Pubkey, // holder which chose reward_mint_ata, later holders claim to their ata
+    pub rent_payer: Pubkey, // paid the rent of the stake account and its NFT account, which is refunded to it on unstake, Pubkey::default() for positions staked before it was recorded
+    pub left_set: bool, // the position no longer counts in the set of the staker, which sold its receipt
+    pub boost_bps: u16, // boost of locked_boost under the tiers in force at staked_at, applied until the next checkpoint
+    pub revenue_carry: u64, // unpaid remainder of the revenue share, in units of 1 / REVENUE_SCALE lamports
+    pub rent_vault_sponsored: bool, // rent_payer is the rent vault of the pool, which did not pay the receipt account
+    pub reserved: [u8; STAKE_RESERVED_LEN], // zeroed room for new fields, so the size of existing stake accounts never changes
+}
+
+// new fields take their bytes from `reserved` and must treat zero as their default
+pub const STAKE_RESERVED_LEN: usize = 116;
+
+/// Whole reward tokens settled for the pool reward token and each reward stream.
+pub struct SettledRewards {
+    pub reward_amount: u64,
+    pub stream_amounts: [u64; MAX_REWARD_STREAMS],
+}
+
+impl SettledRewards {
+    /// `bps` of the settled pool reward tokens, rounded down. The reward streams are not shared,
+    /// their vaults are funded for the stakers only.
+    pub fn share(&self, bps: u16) -> u64 {
+        (self.reward_amount as u128 * bps as u128 / BASIS_POINTS as u128) as u64
+    }
 }
 
 impl Stake {
-    pub const LEN: usize = 32 + 32 + 32 + 32 + 8;
-}
\ No newline at end of file
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 8 + 16 + 8 + 8 + 16 * MAX_REWARD_STREAMS + 32 + 32 + 1 + 1 + 1 + 16 + 8 + 2 + 8 + 16 + 32 + 32 + 32 + 32 + 32 + 32 + 1 + 2 + 8 + 1 + STAKE_RESERVED_LEN;
+
+    pub fn signer_seeds(&self) -> [&[u8]; 5] {
+        [
+            b"stake",
+            self.stake_details_key.as_ref(),
+            self.nft_mint.as_ref(),
+            self.staker.as_ref(),
+            std::slice::from_ref(&self.bump_seed),
+        ]
+    }
+
+    pub fn is_unbonding(&self) -> bool {
+        self.unbonding_ends_at != 0
+    }
+
+    pub fn is_guild_position(&self) -> bool {
+        self.guild != Pubkey::default()
+    }
+
+    /// Whether `delegate` may claim the rewards of the position while `holder` holds its receipt.
+    pub fn is_claim_delegate(&self, delegate: &Pubkey, holder: &Pubkey) -> bool {
+        self.claim_delegate != Pubkey::default() && self.claim_delegate == *delegate && self.delegated_by == *holder
+    }
+
+    /// The reward token account the rewards of `holder` are claimed to: the one it chose, else its ata.
+    pub fn reward_destination(&self, holder: &Pubkey, holder_ata: Pubkey) -> Pubkey {
+        if self.reward_destination_holder == *holder {
+            self.reward_mint_ata
+        } else {
+            holder_ata
+        }
+    }
+
+    /// Takes the position out of the set of its staker for good once `holder`, which is not the staker, holds its
+    /// receipt: the set bonus rewards the wallet staking the NFTs, the seller must not keep it. Returns whether
+    /// the position left the set now, the staked count of the staker must then be decremented.
+    pub fn leave_set_if_sold(&mut self, holder: &Pubkey) -> bool {
+        let sold = !self.left_set && self.staker != *holder;
+        self.left_set |= sold;
+        sold
+    }
+
+    /// Stops paying the rewards of a guild position to its guild once `holder`, which is not the staker, holds its
+    /// receipt: the staker chose the guild, a buyer is paid the rewards of the position it holds.
+    pub fn leave_guild_if_sold(&mut self, holder: &Pubkey) {
+        if self.is_guild_position() && self.staker != *holder {
+            self.guild = Pubkey::default();
+        }
+    }
+
+    pub fn is_milestone_claimed(&self, index: usize) -> bool {
+        self.milestones_claimed & (1 << index) != 0
+    }
+
+    pub fn is_potion_active(&self, now: i64) -> bool {
+        now < self.potion_expires_at
+    }
+
+    /// Accrues the rewards from `staked_at` until `now` and returns the whole tokens to be paid.
+    /// The fractional remainders are kept in the carries, so many small claims pay
+    /// exactly the same as a single big one. The boost index of `staker` must be updated until `now`,
+    /// the set bonus only applies to the pool reward token and to positions still in the set of their staker.
+    pub fn settle_rewards(&mut self, stake_details: &StakeDetails, staker: &Staker, now: i64) -> Result<SettledRewards, ProgramError> {
+
+        // reward_rate * sum of bonus_bps * seconds since the last settlement, in the units of the carry
+        let set_bonus = if self.left_set {
+            0
+        } else {
+            staker.boost_index
+                .checked_sub(self.boost_index_snapshot)
+                .and_then(|bonus| bonus.checked_mul(stake_details.reward_rate as u128))
+                .map(|bonus| bonus / BASIS_POINTS as u128)
+                .ok_or(NftStakingError::RewardOverflow)?
+        };
+
+        self.reward_carry = self.reward_carry.checked_add(set_bonus).ok_or(NftStakingError::RewardOverflow)?;
+        self.boost_index_snapshot = staker.boost_index;
+
+        self.checkpoint_rewards(stake_details, now)?;
+
+        let (reward_amount, reward_carry) = split_carry(self.reward_carry, stake_details.reward_period)?;
+        self.reward_carry = reward_carry;
+
+        let mut stream_amounts = [0; MAX_REWARD_STREAMS];
+
+        for (index, stream) in stake_details.active_reward_streams().iter().enumerate() {
+            let (amount, carry) = split_carry(self.stream_carries[index], stream.reward_period)?;
+
+            stream_amounts[index] = amount;
+            self.stream_carries[index] = carry;
+        }
+
+        Ok(SettledRewards { reward_amount, stream_amounts })
+    }
+
+    /// Moves the rewards accrued from `staked_at` until `now` into the carries without paying them,
+    /// so that a change of the locked boost, of the boost tiers or a new potion only applies from `now` on.
+    pub fn checkpoint_rewards(&mut self, stake_details: &StakeDetails, now: i64) -> Result<(), ProgramError> {
+
+        let boost_bps = self.boost_bps as u128;
+
+        // the potion only boosts the part of the period before it expired
+        let potion_duration = now.min(self.potion_expires_at).saturating_sub(self.staked_at);
+
+        let reward_accrued = accrue(now.saturating_sub(self.staked_at), stake_details.reward_rate)?
+            .checked_mul(BASIS_POINTS as u128 + boost_bps)
+            .zip(accrue(potion_duration, stake_details.reward_rate)?.checked_mul(self.potion_boost_bps as u128))
+            .and_then(|(boosted, potion)| boosted.checked_add(potion))
+            .map(|accrued| accrued / BASIS_POINTS as u128)
+            .ok_or(NftStakingError::RewardOverflow)?;
+
+        self.reward_carry = self.reward_carry.checked_add(reward_accrued).ok_or(NftStakingError::RewardOverflow)?;
+
+        for (index, stream) in stake_details.active_reward_streams().iter().enumerate() {
+
+            // streams added after the stake only accrue from their start
+            let accrual_start = self.staked_at.max(stream.start_time);
+            let stream_accrued = accrue(now.saturating_sub(accrual_start), stream.reward_rate)?;
+
+            self.stream_carries[index] = self.stream_carries[index]
+                .checked_add(stream_accrued)
+                .ok_or(NftStakingError::RewardOverflow)?;
+        }
+
+        self.staked_at = now;
+        self.set_locked_boost(stake_details, self.locked_boost);
+
+        Ok(())
+    }
+
+    /// Sets the locked boost and locks its boost under the current tiers until the next checkpoint.
+    /// The rewards must be checkpointed until now first, so a new boost never applies to the past.
+    pub fn set_locked_boost(&mut self, stake_details: &StakeDetails, locked_boost: u64) {
+        self.locked_boost = locked_boost;
+        self.boost_bps = stake_details.lock_boost_bps(locked_boost);
+    }
+
+    /// Keeps stream rewards which could not be paid (e.g. an empty vault) owed for a later claim.
+    pub fn defer_stream_rewards(&mut self, index: usize, stream: &RewardStream, unpaid_amount: u64) -> Result<(), ProgramError> {
+
+        self.stream_carries[index] = (unpaid_amount as u128)
+            .checked_mul(reward_denominator(stream.reward_period)?)
+            .and_then(|amount| amount.checked_add(self.stream_carries[index]))
+            .ok_or(NftStakingError::RewardOverflow)?;
+
+        Ok(())
+    }
+}
+
+fn reward_denominator(reward_period: i64) -> Result<u128, ProgramError> {
+    let denominator = (reward_period as u128)
+        .checked_mul(REWARD_RATE_SCALE)
+        .filter(|denominator| *denominator != 0)
+        .ok_or(NftStakingError::InvalidRewardRate)?;
+
+    Ok(denominator)
+}
+
+/// Returns the amount accrued at `reward_rate` over `duration` seconds, in the units of the carry.
+fn accrue(duration: i64, reward_rate: u64) -> Result<u128, ProgramError> {
+    let accrued = (duration.max(0) as u128)
+        .checked_mul(reward_rate as u128)
+        .ok_or(NftStakingError::RewardOverflow)?;
+
+    Ok(accrued)
+}
+
+/// Splits a carry into the whole tokens it holds and the remainder.
+fn split_carry(carry: u128, reward_period: i64) -> Result<(u64, u128), ProgramError> {
+
+    let denominator = reward_denominator(reward_period)?;
+
+    let reward_amount = u64::try_from(carry / denominator).map_err(|_| NftStakingError::RewardOverflow)?;
+
+    Ok((reward_amount, carry % denominator))
+}
@@ -1,15 +1,175 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 
+use super::{BoostTier, Level, Milestone, RewardStream, SetBonusTier, MAX_BOOST_TIERS, MAX_LEVELS, MAX_MILESTONES, MAX_REWARD_STREAMS, MAX_SET_BONUS_TIERS};
+
+// `reward_rate` is a fixed-point number with 9 decimal places
+pub const REWARD_RATE_SCALE: u128 = 1_000_000_000;
+
+pub const BASIS_POINTS: u64 = 10_000;
+
+// `revenue_per_share` is a fixed-point number with 12 decimal places
+pub const REVENUE_SCALE: u128 = 1_000_000_000_000;
+
+// upper bound of the protocol fee a pool can be configured with
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+// upper bound of the share of the rewards a crank can take as tip
+pub const MAX_CRANK_TIP_BPS: u16 = 500;
+
+// new fields take their bytes from `reserved` and must treat zero as their default
+pub const STAKE_DETAILS_RESERVED_LEN: usize = 96;
+
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct StakeDetails {
     pub creator: Pubkey,
     pub reward_token_mint: Pubkey,
     pub collection_mint: Pubkey,
     pub bump_seed: u8,
+    pub reward_rate: u64,   // reward tokens (in base units, scaled by REWARD_RATE_SCALE) released per period
+    pub reward_period: i64, // length of a reward period in seconds
+    pub treasury: Pubkey,   // reward token account owned by the stake details pda, emptied by the creator through WithdrawTreasury
+    pub min_stake_duration: i64,        // unstaking before this many seconds is penalized
+    pub early_unstake_penalty_bps: u16, // share of the rewards forfeited on early unstake
+    pub early_unstake_flat_fee: u64,    // reward tokens forfeited on early unstake
+    pub unbonding_period: i64,          // 0 for single-shot unstake, else seconds between RequestUnstake and WithdrawNft
+    pub fee_treasury: Pubkey,           // reward token account receiving the protocol fee
+    pub protocol_fee_bps: u16,          // share of every reward payout taken as protocol fee
+    pub protocol_fee_cap: u64,          // max protocol fee per payout, 0 for no cap
+    pub reward_stream_count: u8,
+    pub reward_streams: [RewardStream; MAX_REWARD_STREAMS], // only the first reward_stream_count are in use
+    pub badge_mint: Pubkey, // soulbound Token-2022 badge, one per staked position of a wallet
+    pub milestone_collection: Pubkey, // collection of the milestone NFTs, Pubkey::default() until configured
+    pub milestone_count: u8,
+    pub milestones: [Milestone; MAX_MILESTONES], // sorted by duration, only the first milestone_count are in use
+    pub level_count: u8,
+    pub levels: [Level; MAX_LEVELS], // sorted by duration, only the first level_count are in use
+    pub set_bonus_tier_count: u8,
+    pub set_bonus_tiers: [SetBonusTier; MAX_SET_BONUS_TIERS], // sorted by min_staked, only the first set_bonus_tier_count are in use
+    pub boost_tier_count: u8,
+    pub boost_tiers: [BoostTier; MAX_BOOST_TIERS], // sorted by min_locked, only the first boost_tier_count are in use
+    pub potion_price: u64, // reward tokens burnt for a potion, 0 if the pool does not sell potions
+    pub potion_boost_bps: u16,
+    pub potion_duration: i64,
+    pub revenue_vault: Pubkey, // lamport vault of the SOL revenue share, Pubkey::default() until the first deposit
+    pub revenue_per_share: u128, // lamports deposited per staked NFT since the pool was created, see REVENUE_SCALE
+    pub total_staked: u64, // NFTs currently staked and not unbonding
+    pub vesting_period: i64, // 0 pays the rewards out directly, else they vest linearly over this many seconds
+    pub instant_withdraw_penalty_bps: u16, // share of the still locked rewards forfeited when withdrawn early
+    pub referral_bps: u16, // paid to the referrer of a staker on top of the rewards of the staker
+    pub crank_tip_bps: u16, // share of the rewards claimed by a crank which is paid to the cranker
+    pub sponsors_rent: bool, // the rent vault of the pool pays the rent of the accounts of new positions closed on unstake
+    pub reward_token_program: Pubkey, // owner of the reward mint, Pubkey::default() for points-only pools and pools created before it was recorded
+    pub reserved: [u8; STAKE_DETAILS_RESERVED_LEN], // zeroed room for new fields, so the size of existing pools never changes
 }
 
 impl StakeDetails {
-    // As each data is 32 bytes
-    pub const LEN:usize = 32 + 32 + 32 + 1;
+    // As each key is 32 bytes
+    pub const LEN:usize = 32 + 32 + 32 + 1 + 8 + 8 + 32 + 8 + 2 + 8 + 8 + 32 + 2 + 8 + 1 + RewardStream::LEN * MAX_REWARD_STREAMS + 32 + 32 + 1 + Milestone::LEN * MAX_MILESTONES + 1 + Level::LEN * MAX_LEVELS + 1 + SetBonusTier::LEN * MAX_SET_BONUS_TIERS + 1 + BoostTier::LEN * MAX_BOOST_TIERS + 8 + 2 + 8 + 32 + 16 + 8 + 8 + 2 + 2 + 2 + 1 + 32 + STAKE_DETAILS_RESERVED_LEN;
+
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            b"stake_details",
+            self.creator.as_ref(),
+            self.collection_mint.as_ref(),
+            std::slice::from_ref(&self.bump_seed),
+        ]
+    }
+
+    pub fn active_reward_streams(&self) -> &[RewardStream] {
+        &self.reward_streams[..self.reward_stream_count as usize]
+    }
+
+    pub fn active_milestones(&self) -> &[Milestone] {
+        &self.milestones[..self.milestone_count as usize]
+    }
+
+    pub fn active_levels(&self) -> &[Level] {
+        &self.levels[..self.level_count as usize]
+    }
+
+    /// Points-only pools accrue points on the staker accounts until a reward mint is attached.
+    pub fn is_points_only(&self) -> bool {
+        self.reward_token_mint == Pubkey::default()
+    }
+
+    /// Token program of the reward mint, which also holds the receipts of the positions.
+    /// Pools without a recorded one use the SPL Token program.
+    pub fn token_program_id(&self) -> Pubkey {
+        if self.reward_token_program == Pubkey::default() {
+            return spl_token::id();
+        }
+
+        self.reward_token_program
+    }
+
+    /// Vesting pools credit the rewards to the vesting escrow of the holder instead of minting them.
+    pub fn is_vesting(&self) -> bool {
+        self.vesting_period > 0
+    }
+
+    /// Bonus of the highest tier reached by a wallet with `staked_count` positions in the pool.
+    pub fn set_bonus_bps(&self, staked_count: u8) -> u16 {
+        self.set_bonus_tiers[..self.set_bonus_tier_count as usize]
+            .iter()
+            .rev()
+            .find(|tier| staked_count >= tier.min_staked)
+            .map_or(0, |tier| tier.bonus_bps)
+    }
+
+    /// Boost of the highest tier reached by a position locking `locked` reward tokens.
+    pub fn lock_boost_bps(&self, locked: u64) -> u16 {
+        self.boost_tiers[..self.boost_tier_count as usize]
+            .iter()
+            .rev()
+            .find(|tier| locked >= tier.min_locked)
+            .map_or(0, |tier| tier.boost_bps)
+    }
+
+    /// Lamports of the revenue share owed to a position last paid at `revenue_index_snapshot` with `revenue_carry`
+    /// left unpaid, and the remainder to carry over to its next payout.
+    pub fn revenue_owed(&self, revenue_index_snapshot: u128, revenue_carry: u64) -> (u64, u64) {
+        let accrued = self.revenue_per_share
+            .saturating_sub(revenue_index_snapshot)
+            .saturating_add(revenue_carry as u128);
+
+        ((accrued / REVENUE_SCALE) as u64, (accrued % REVENUE_SCALE) as u64)
+    }
+
+    /// Level of an NFT with the given metadata uri, 0 when it has not evolved yet.
+    pub fn level_of(&self, uri: &str) -> u8 {
+        let uri = uri.trim_end_matches('\0');
+
+        self.active_levels()
+            .iter()
+            .rposition(|level| level.uri() == uri)
+            .map_or(0, |index| index as u8 + 1)
+    }
+
+    /// Reward tokens forfeited to the treasury when unstaking before `min_stake_duration`.
+    /// The penalty never exceeds the rewards being paid out.
+    pub fn early_unstake_penalty(&self, reward_amount: u64, staked_for: i64) -> u64 {
+
+        if staked_for >= self.min_stake_duration {
+            return 0;
+        }
+
+        let percentage = reward_amount as u128 * self.early_unstake_penalty_bps as u128 / BASIS_POINTS as u128;
+
+        (percentage as u64)
+            .saturating_add(self.early_unstake_flat_fee)
+            .min(reward_amount)
+    }
+
+    /// Reward tokens taken from a payout as protocol fee.
+    pub fn protocol_fee(&self, reward_amount: u64) -> u64 {
+
+        let fee = (reward_amount as u128 * self.protocol_fee_bps as u128 / BASIS_POINTS as u128) as u64;
+
+        if self.protocol_fee_cap == 0 {
+            return fee;
+        }
+
+        fee.min(self.protocol_fee_cap)
+    }
 }
@@ -0,0 +1,63 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::errors::NftStakingError;
+
+use super::StakeDetails;
+
+/// Aggregate of a wallet in a pool, at ["staker", stake_details, owner]: its staked positions, points and referrer.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Staker {
+    pub stake_details_key: Pubkey,
+    pub owner: Pubkey,
+    pub staked_count: u8,
+    pub boost_index: u128, // sum of set_bonus_bps * seconds, positions accrue the bonus from their snapshot of it
+    pub last_update: i64,
+    pub bump_seed: u8,
+    pub points: u64, // earned in a points-only pool, convertible into reward tokens once the pool has a mint
+    pub referrer: Pubkey, // wallet which referred the owner to the pool, Pubkey::default() if none
+    pub set_bonus_bps: u16, // bonus of staked_count under the tiers in force at last_update, accrued until the next update
+    pub reserved: [u8; STAKER_RESERVED_LEN], // zeroed room for new fields, so the size of existing staker accounts never changes
+}
+
+// new fields take their bytes from `reserved` and must treat zero as their default
+pub const STAKER_RESERVED_LEN: usize = 62;
+
+impl Staker {
+    pub const LEN: usize = 32 + 32 + 1 + 16 + 8 + 1 + 8 + 32 + 2 + STAKER_RESERVED_LEN;
+
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            b"staker",
+            self.stake_details_key.as_ref(),
+            self.owner.as_ref(),
+            std::slice::from_ref(&self.bump_seed),
+        ]
+    }
+
+    pub fn has_referrer(&self) -> bool {
+        self.referrer != Pubkey::default()
+    }
+
+    /// Accrues the set bonus locked at the last update until `now`.
+    /// Must be called before `set_staked_count`, so neither a new count nor new tiers ever apply to the past.
+    pub fn update_boost_index(&mut self, now: i64) -> Result<(), ProgramError> {
+
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u128;
+
+        self.boost_index = (self.set_bonus_bps as u128)
+            .checked_mul(elapsed)
+            .and_then(|accrued| accrued.checked_add(self.boost_index))
+            .ok_or(NftStakingError::RewardOverflow)?;
+
+        self.last_update = now;
+
+        Ok(())
+    }
+
+    /// Sets the staked count and locks its bonus under the current tiers until the next update.
+    pub fn set_staked_count(&mut self, stake_details: &StakeDetails, staked_count: u8) {
+        self.staked_count = staked_count;
+        self.set_bonus_bps = stake_details.set_bonus_bps(staked_count);
+    }
+}
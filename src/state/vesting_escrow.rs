@@ -0,0 +1,64 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::errors::NftStakingError;
+
+/// Rewards of a wallet vesting in a pool, at ["vesting", stake_details, owner].
+/// The rewards are minted when withdrawn, the escrow only records them.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct VestingEscrow {
+    pub stake_details_key: Pubkey,
+    pub owner: Pubkey,
+    pub locked_amount: u64, // released linearly from last_update until vesting_ends_at
+    pub vested_amount: u64, // released and not withdrawn yet
+    pub last_update: i64,
+    pub vesting_ends_at: i64,
+    pub bump_seed: u8,
+    pub reserved: [u8; VESTING_ESCROW_RESERVED_LEN], // zeroed room for new fields, so the size of existing escrows never changes
+}
+
+// new fields take their bytes from `reserved` and must treat zero as their default
+pub const VESTING_ESCROW_RESERVED_LEN: usize = 64;
+
+impl VestingEscrow {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 1 + VESTING_ESCROW_RESERVED_LEN;
+
+    /// Releases the part of the locked rewards vested until `now`.
+    pub fn update(&mut self, now: i64) -> Result<(), ProgramError> {
+
+        if now >= self.vesting_ends_at {
+            self.vested_amount = self.vested_amount.checked_add(self.locked_amount).ok_or(NftStakingError::RewardOverflow)?;
+            self.locked_amount = 0;
+        } else if now > self.last_update {
+            let released = self.locked_amount as u128 * (now - self.last_update) as u128 / (self.vesting_ends_at - self.last_update) as u128;
+
+            self.locked_amount -= released as u64;
+            self.vested_amount = self.vested_amount.checked_add(released as u64).ok_or(NftStakingError::RewardOverflow)?;
+        }
+
+        self.last_update = self.last_update.max(now);
+
+        Ok(())
+    }
+
+    /// Locks `amount` for `vesting_period` seconds. The rewards already locked keep vesting,
+    /// the schedule ends at the average of both ends weighted by the amounts.
+    pub fn deposit(&mut self, amount: u64, vesting_period: i64, now: i64) -> Result<(), ProgramError> {
+
+        self.update(now)?;
+
+        let locked_amount = self.locked_amount.checked_add(amount).ok_or(NftStakingError::RewardOverflow)?;
+
+        if locked_amount == 0 {
+            return Ok(());
+        }
+
+        let remaining = self.vesting_ends_at.saturating_sub(now).max(0) as u128;
+        let duration = (self.locked_amount as u128 * remaining + amount as u128 * vesting_period.max(0) as u128) / locked_amount as u128;
+
+        self.locked_amount = locked_amount;
+        self.vesting_ends_at = now + duration as i64;
+
+        Ok(())
+    }
+}
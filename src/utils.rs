@@ -0,0 +1,1201 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar
+};
+use spl_associated_token_account::{get_associated_token_address_with_program_id, instruction::{create_associated_token_account, create_associated_token_account_idempotent}};
+use spl_token::{instruction::{burn, close_account, mint_to_checked, set_authority, transfer, AuthorityType}, state::{Account, Mint}};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{errors::NftStakingError, state::{FundingMode, Guild, ProtocolConfig, SettledRewards, Stake, StakeDetails, Staker, VestingEscrow, BASIS_POINTS, MAX_REWARD_STREAMS, STAKER_RESERVED_LEN, VESTING_ESCROW_RESERVED_LEN}};
+
+/// Mints reward tokens of a pool, signed by the stake details pda as the mint authority.
+/// Points-only pools credit points instead, see `credit_points`, and vesting pools vest the rewards, see `vest_rewards`.
+pub struct RewardMinter<'a, 'b> {
+    pub program_id: &'b Pubkey,
+    pub token_program: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub reward_mint_account: &'b AccountInfo<'a>,
+    pub stake_details_account: &'b AccountInfo<'a>,
+    pub stake_details: &'b StakeDetails,
+    pub fee_treasury_account: &'b AccountInfo<'a>,
+    pub rent_payer: Option<&'b AccountInfo<'a>>, // pays the rent of the accounts created for the holder instead of the holder
+}
+
+impl<'a> RewardMinter<'a, '_> {
+
+    pub fn mint_to(&self, destination: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+        self.mint_tokens(self.reward_mint_account, destination, amount)
+    }
+
+    fn mint_tokens(&self, mint_account: &AccountInfo<'a>, destination: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+
+        let mint_data = Mint::unpack(&mint_account.data.borrow())?;
+
+        msg!("minting {} tokens to {}", amount, destination.key);
+
+        let ix = mint_to_checked(
+            self.token_program.key, 
+            mint_account.key, 
+            destination.key, 
+            self.stake_details_account.key,     // as stake_details is the mint authority of the reward token
+            &[self.stake_details_account.key], 
+            amount, 
+            mint_data.decimals,
+        )?;
+
+        invoke_signed(
+            &ix, 
+            &[
+                self.stake_details_account.clone(),
+                destination.clone(),
+                mint_account.clone(),
+            ], 
+            &[&self.stake_details.signer_seeds()]
+        )
+    }
+
+    /// Transfers up to `amount` tokens out of a vault owned by the stake details pda and returns the amount paid.
+    fn transfer_from_vault(&self, vault: &AccountInfo<'a>, destination: &AccountInfo<'a>, amount: u64) -> Result<u64, ProgramError> {
+
+        let vault_balance = Account::unpack(&vault.data.borrow())?.amount;
+        let paid_amount = amount.min(vault_balance);
+
+        msg!("transferring {} of {} tokens from the vault {} to {}", paid_amount, amount, vault.key, destination.key);
+
+        if paid_amount == 0 {
+            return Ok(0);
+        }
+
+        let ix = transfer(
+            self.token_program.key, 
+            vault.key, 
+            destination.key, 
+            self.stake_details_account.key, 
+            &[self.stake_details_account.key], 
+            paid_amount
+        )?;
+
+        invoke_signed(
+            &ix, 
+            &[
+                self.stake_details_account.clone(),
+                vault.clone(),
+                destination.clone(),
+                self.token_program.clone(),
+            ], 
+            &[&self.stake_details.signer_seeds()]
+        )?;
+
+        Ok(paid_amount)
+    }
+
+    /// Mints the protocol fee of a payout to the fee treasury and returns the remaining rewards.
+    /// Points are not charged.
+    pub fn take_protocol_fee(&self, reward_amount: u64) -> Result<u64, ProgramError> {
+
+        if self.stake_details.is_points_only() {
+            return Ok(reward_amount);
+        }
+
+        if self.stake_details.fee_treasury != *self.fee_treasury_account.key {
+            msg!("Invalid Fee Treasury Account, expected : {}", self.stake_details.fee_treasury);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let fee_amount = self.stake_details.protocol_fee(reward_amount);
+
+        msg!("Protocol fee : {} of {} reward tokens", fee_amount, reward_amount);
+
+        if fee_amount > 0 {
+            self.mint_to(self.fee_treasury_account, fee_amount)?;
+        }
+
+        Ok(reward_amount - fee_amount)
+    }
+
+    /// Pays `reward_amount` to `holder` after taking the protocol fee.
+    pub fn pay_rewards(&self, holder: &AccountInfo<'a>, destination: &AccountInfo<'a>, reward_amount: u64) -> ProgramResult {
+
+        let user_amount = self.take_protocol_fee(reward_amount)?;
+
+        self.pay_holder(holder, destination, user_amount)
+    }
+
+    /// Pays `reward_amount` settled for `stake` after taking the protocol fee, see `pay_position`.
+    pub fn pay_position_rewards(&self, stake: &Stake, holder: &AccountInfo<'a>, destination: &AccountInfo<'a>, reward_amount: u64) -> ProgramResult {
+
+        let user_amount = self.take_protocol_fee(reward_amount)?;
+
+        self.pay_position(stake, holder, destination, user_amount)
+    }
+
+    /// Mints `reward_amount` claimed by a crank to the reward token account of the holder after taking the
+    /// protocol fee, less the crank tip of the pool which is minted to the cranker.
+    pub fn pay_cranked_rewards(&self, destination: &AccountInfo<'a>, cranker_reward_token_account: &AccountInfo<'a>, reward_amount: u64) -> ProgramResult {
+
+        // the pool signs the tip, it must only go to a reward token account
+        check_reward_token_account(cranker_reward_token_account, self.stake_details)?;
+
+        let user_amount = self.take_protocol_fee(reward_amount)?;
+        let tip_amount = (user_amount as u128 * self.stake_details.crank_tip_bps as u128 / BASIS_POINTS as u128) as u64;
+
+        if tip_amount > 0 {
+            msg!("Crank tip : {} of {} reward tokens", tip_amount, user_amount);
+            self.mint_to(cranker_reward_token_account, tip_amount)?;
+        }
+
+        self.mint_to(destination, user_amount - tip_amount)
+    }
+
+    /// Pays `amount` of the rewards of `stake` to `holder`, see `pay_holder`. The rewards of a guild
+    /// position are credited to the guild instead, `destination` is then the guild account.
+    fn pay_position(&self, stake: &Stake, holder: &AccountInfo<'a>, destination: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+
+        if stake.is_guild_position() {
+            return credit_guild(self.program_id, destination, self.stake_details_account, &stake.guild, amount);
+        }
+
+        self.pay_holder(holder, destination, amount)
+    }
+
+    /// Locks `amount` reward tokens in the vesting escrow of the holder, creating it if needed with the rent paid by `payer`.
+    pub fn vest_rewards(&self, payer: &AccountInfo<'a>, holder: &Pubkey, vesting_escrow_account: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut vesting_escrow = if vesting_escrow_account.data.borrow().len() == 0 {
+            let (vesting_escrow_key, vesting_escrow_bump) = Pubkey::find_program_address(
+                &[b"vesting", self.stake_details_account.key.as_ref(), holder.as_ref()],
+                self.program_id
+            );
+
+            if vesting_escrow_key != *vesting_escrow_account.key {
+                msg!("Vesting Escrow Account expected : {}, received : {}", vesting_escrow_key, vesting_escrow_account.key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key,
+                    vesting_escrow_account.key,
+                    Rent::get()?.minimum_balance(VestingEscrow::LEN),
+                    VestingEscrow::LEN as u64,
+                    self.program_id
+                ),
+                &[payer.clone(), vesting_escrow_account.clone(), self.system_program.clone()],
+                &[&[b"vesting", self.stake_details_account.key.as_ref(), holder.as_ref(), &[vesting_escrow_bump]]]
+            )?;
+
+            msg!("Vesting Escrow account has been created");
+
+            VestingEscrow {
+                stake_details_key: *self.stake_details_account.key,
+                owner: *holder,
+                locked_amount: 0,
+                vested_amount: 0,
+                last_update: now,
+                vesting_ends_at: now,
+                bump_seed: vesting_escrow_bump,
+                reserved: [0; VESTING_ESCROW_RESERVED_LEN],
+            }
+        } else {
+            load_vesting_escrow(self.program_id, vesting_escrow_account, self.stake_details_account, holder)?
+        };
+
+        vesting_escrow.deposit(amount, self.stake_details.vesting_period, now)?;
+        vesting_escrow.serialize(&mut *vesting_escrow_account.data.borrow_mut())?;
+
+        msg!("Vesting {} reward tokens for {} until {}", amount, holder, vesting_escrow.vesting_ends_at);
+
+        Ok(())
+    }
+
+    /// Mints `amount` to `destination`, the reward token account of `holder`. In a points-only pool,
+    /// `destination` is the staker account of `holder` and the amount is credited as points.
+    /// In a vesting pool, `destination` is the vesting escrow of `holder`.
+    fn pay_holder(&self, holder: &AccountInfo<'a>, destination: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+
+        let payer = self.rent_payer.unwrap_or(holder);
+
+        if self.stake_details.is_points_only() {
+            return credit_points(self.program_id, payer, holder, destination, self.stake_details_account, self.reward_mint_account, amount);
+        }
+
+        if self.stake_details.is_vesting() {
+            return self.vest_rewards(payer, holder.key, destination, amount);
+        }
+
+        self.mint_to(destination, amount)
+    }
+}
+
+/// Accounts of the reward token of a pool, set up when the pool is created or, for a
+/// points-only pool, when its reward mint is attached later on.
+pub struct RewardMintSetup<'a, 'b> {
+    pub reward_mint: &'b AccountInfo<'a>,
+    pub treasury_token_account: &'b AccountInfo<'a>, // ata of the stake details account for the reward mint
+    pub fee_treasury_account: &'b AccountInfo<'a>, // reward token account of the protocol treasury
+    pub token_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+}
+
+impl<'a> RewardMintSetup<'a, '_> {
+
+    /// Checks the reward mint and the fee treasury against the protocol config and returns the pool treasury key.
+    pub fn validate(&self, protocol_config: &ProtocolConfig, stake_details_key: &Pubkey) -> Result<Pubkey, ProgramError> {
+
+        if !protocol_config.is_token_program_allowed(self.token_program.key) || self.reward_mint.owner != self.token_program.key {
+            msg!("Token Program {} is not allowed for the reward mint owned by {}", self.token_program.key, self.reward_mint.owner);
+            return Err(NftStakingError::TokenProgramNotAllowed.into());
+        }
+
+        let fee_treasury_data = Account::unpack(&self.fee_treasury_account.data.borrow())?;
+
+        if fee_treasury_data.mint != *self.reward_mint.key {
+            msg!("Fee Treasury Account mint expected : {}, received : {}", self.reward_mint.key, fee_treasury_data.mint);
+            return Err(NftStakingError::RewardMintMismatch.into());
+        }
+
+        if fee_treasury_data.owner != protocol_config.treasury {
+            msg!("Fee Treasury Account must be owned by the protocol treasury : {}", protocol_config.treasury);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let treasury_key = get_associated_token_address_with_program_id(
+            stake_details_key,
+            self.reward_mint.key,
+            self.token_program.key
+        );
+
+        if treasury_key != *self.treasury_token_account.key {
+            msg!("Treasury Token Account Mismatch");
+            msg!("Treasury Token Account expected : {}, received : {}", treasury_key, self.treasury_token_account.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(treasury_key)
+    }
+
+    /// Creates the pool treasury and hands the mint authority of the reward mint over to the pool.
+    pub fn attach(&self, creator: &AccountInfo<'a>, stake_details_account: &AccountInfo<'a>) -> ProgramResult {
+
+        let treasury_ix = create_associated_token_account(
+            creator.key,
+            stake_details_account.key,
+            self.reward_mint.key,
+            self.token_program.key
+        );
+
+        invoke(
+            &treasury_ix,
+            &[
+                creator.clone(),
+                self.treasury_token_account.clone(),
+                stake_details_account.clone(),
+                self.reward_mint.clone(),
+                self.system_program.clone(),
+                self.token_program.clone(),
+                self.associated_token_program.clone(),
+            ]
+        )?;
+
+        msg!("Successfully Created Treasury Token Account");
+
+        let mint_authority_ix = set_authority(
+            self.token_program.key, 
+            self.reward_mint.key, 
+            Some(stake_details_account.key), 
+            AuthorityType::MintTokens,   // set as mint authority
+            creator.key, 
+            &[creator.key]
+        )?;
+
+        invoke( 
+            &mint_authority_ix,
+            &[
+                creator.clone(), 
+                stake_details_account.clone(), 
+                self.token_program.clone(), 
+                self.reward_mint.clone()
+            ]
+        )?;
+
+        msg!("Successfully transfered mint authority to stake details account");
+
+        Ok(())
+    }
+}
+
+/// Points-only pools pass the system program, which can not be writable, in place of their token accounts.
+pub fn is_writable_or_unused(account: &AccountInfo) -> bool {
+    account.is_writable || *account.key == Pubkey::default()
+}
+
+/// Loads the protocol config and checks its address.
+pub fn load_protocol_config(program_id: &Pubkey, protocol_config_account: &AccountInfo) -> Result<ProtocolConfig, ProgramError> {
+
+    if protocol_config_account.owner != program_id {
+        msg!("Protocol Config Account is not owned by the program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let protocol_config = ProtocolConfig::try_from_slice(&protocol_config_account.data.borrow())?;
+
+    let protocol_config_key = Pubkey::create_program_address(
+        &[b"protocol_config", &[protocol_config.bump_seed]],
+        program_id
+    )?;
+
+    if protocol_config_key != *protocol_config_account.key {
+        msg!("Protocol Config expected : {}, received : {}", protocol_config_key, protocol_config_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(protocol_config)
+}
+
+/// Pays the reward streams of the pool to `owner`. For every active stream the accounts must hold the
+/// source (the stream mint in Mint mode, the vault in Vault mode) followed by a token account of `owner` for the stream mint.
+/// Whatever an empty vault cannot pay stays owed in the stake.
+pub fn pay_reward_streams<'a, 'b, I>(
+    minter: &RewardMinter<'a, '_>,
+    stake: &mut Stake,
+    stream_amounts: &[u64; MAX_REWARD_STREAMS],
+    owner: &Pubkey,
+    accounts: &mut I,
+) -> ProgramResult
+where
+    'a: 'b,
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let unpaid_amounts = transfer_reward_streams(minter, stream_amounts, owner, accounts)?;
+
+    for (index, stream) in minter.stake_details.active_reward_streams().iter().enumerate() {
+        if unpaid_amounts[index] > 0 {
+            msg!("Reward stream {} vault is short, {} tokens stay owed", index, unpaid_amounts[index]);
+            stake.defer_stream_rewards(index, stream, unpaid_amounts[index])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pays `referral_amount`, the share of the rewards of `staker` owed to its referrer, on top of the rewards and
+/// after the protocol fee. The accounts must hold a reward token account of the referrer, or its vesting escrow
+/// in a vesting pool, created with the rent paid by `holder` or its sponsor.
+/// Takes no accounts unless the staker has a referrer and the pool pays referrals, points-only pools do not.
+pub fn pay_referral<'a, 'b, I>(
+    minter: &RewardMinter<'a, '_>,
+    holder: &AccountInfo<'a>,
+    staker: &Staker,
+    referral_amount: u64,
+    accounts: &mut I,
+) -> ProgramResult
+where
+    'a: 'b,
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let stake_details = minter.stake_details;
+
+    if !staker.has_referrer() || stake_details.referral_bps == 0 || stake_details.is_points_only() {
+        return Ok(());
+    }
+
+    let referrer = &staker.referrer;
+    let referrer_reward_account = next_account_info(accounts)?;
+
+    if !referrer_reward_account.is_writable {
+        msg!("Referrer Reward Token Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the vesting escrow is checked when credited
+    if !stake_details.is_vesting() {
+        let referrer_reward_data = Account::unpack(&referrer_reward_account.data.borrow())?;
+
+        if referrer_reward_data.mint != stake_details.reward_token_mint || referrer_reward_data.owner != *referrer {
+            msg!("Referral must be paid to a reward token account of {}", referrer);
+            return Err(NftStakingError::RewardMintMismatch.into());
+        }
+    }
+
+    msg!("Paying {} reward tokens to the referrer {}", referral_amount, referrer);
+
+    if referral_amount == 0 {
+        return Ok(());
+    }
+
+    let referrer_amount = minter.take_protocol_fee(referral_amount)?;
+
+    if stake_details.is_vesting() {
+        return minter.vest_rewards(minter.rent_payer.unwrap_or(holder), referrer, referrer_reward_account, referrer_amount);
+    }
+
+    minter.mint_to(referrer_reward_account, referrer_amount)
+}
+
+/// Pays `stream_amounts` out of the reward stream accounts, see `pay_reward_streams`, and returns what could not be paid.
+fn transfer_reward_streams<'a, 'b, I>(
+    minter: &RewardMinter<'a, '_>,
+    stream_amounts: &[u64; MAX_REWARD_STREAMS],
+    owner: &Pubkey,
+    accounts: &mut I,
+) -> Result<[u64; MAX_REWARD_STREAMS], ProgramError>
+where
+    'a: 'b,
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let mut unpaid_amounts = [0; MAX_REWARD_STREAMS];
+
+    for (index, stream) in minter.stake_details.active_reward_streams().iter().enumerate() {
+
+        let source = next_account_info(accounts)?;
+        let destination = next_account_info(accounts)?;
+
+        if !source.is_writable || !destination.is_writable {
+            msg!("Reward stream accounts are not writable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let destination_data = Account::unpack(&destination.data.borrow())?;
+
+        if destination_data.mint != stream.mint || destination_data.owner != *owner {
+            msg!("Reward stream {} must be paid to a token account of {} for the mint {}", index, owner, stream.mint);
+            return Err(NftStakingError::RewardMintMismatch.into());
+        }
+
+        let amount = stream_amounts[index];
+
+        if amount == 0 {
+            continue;
+        }
+
+        match stream.funding_mode {
+            FundingMode::Mint => {
+                if *source.key != stream.mint {
+                    msg!("Reward stream {} mint expected : {}, received : {}", index, stream.mint, source.key);
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                minter.mint_tokens(source, destination, amount)?;
+            }
+            FundingMode::Vault => {
+                if *source.key != stream.vault {
+                    msg!("Reward stream {} vault expected : {}, received : {}", index, stream.vault, source.key);
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                let paid_amount = minter.transfer_from_vault(source, destination, amount)?;
+                unpaid_amounts[index] = amount - paid_amount;
+            }
+        }
+    }
+
+    Ok(unpaid_amounts)
+}
+
+/// Pays the rewards settled for a stake which is leaving the pool to the user after the
+/// protocol fee, forfeiting the early unstake penalty (if any) to the pool treasury.
+/// Fails when a reward stream vault is short, as nothing stays owed to a position which stops accruing.
+pub fn pay_unstake_rewards<'a, 'b, I>(
+    minter: &RewardMinter<'a, '_>,
+    stake: &Stake,
+    settled: SettledRewards,
+    user_account: &AccountInfo<'a>,
+    user_reward_token_account: &AccountInfo<'a>,
+    treasury_token_account: &AccountInfo<'a>,
+    reward_stream_accounts: &mut I,
+) -> ProgramResult
+where
+    'a: 'b,
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+
+    let stake_details = minter.stake_details;
+
+    // points-only pools have no treasury, the penalty is not credited at all
+    if !stake_details.is_points_only() && stake_details.treasury != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account, expected : {}", stake_details.treasury);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_amount = minter.take_protocol_fee(settled.reward_amount)?;
+
+    // settle_rewards moved staked_at to the current time
+    let staked_for = stake.staked_at - stake.initial_staked_at;
+    let penalty_amount = stake_details.early_unstake_penalty(reward_amount, staked_for);
+
+    if staked_for < stake_details.min_stake_duration {
+        msg!(
+            "Unstaked after {} seconds, before the minimum of {} seconds. Penalty : {} of {} reward tokens",
+            staked_for,
+            stake_details.min_stake_duration,
+            penalty_amount,
+            reward_amount
+        );
+    }
+
+    minter.pay_position(stake, user_account, user_reward_token_account, reward_amount - penalty_amount)?;
+
+    if penalty_amount > 0 && !stake_details.is_points_only() {
+        msg!("Sending the penalty to the treasury");
+        minter.mint_to(treasury_token_account, penalty_amount)?;
+    }
+
+    let unpaid_amounts = transfer_reward_streams(minter, &settled.stream_amounts, user_account.key, reward_stream_accounts)?;
+
+    if let Some(index) = unpaid_amounts.iter().position(|amount| *amount > 0) {
+        msg!("Reward stream {} vault is short of {} tokens, it must be topped up before the position can leave", index, unpaid_amounts[index]);
+        return Err(NftStakingError::RewardStreamVaultShort.into());
+    }
+
+    Ok(())
+}
+
+/// Returns the reward tokens locked in the boost vault of a closing position to the user
+/// and closes the vault, refunding its rent to the user.
+pub fn unlock_boost<'a>(
+    user_account: &AccountInfo<'a>,
+    user_reward_token_account: &AccountInfo<'a>,
+    stake_account: &AccountInfo<'a>,
+    boost_vault_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stake: &Stake,
+) -> ProgramResult {
+
+    let boost_vault_key = get_associated_token_address_with_program_id(
+        stake_account.key,
+        &stake.reward_mint,
+        token_program.key
+    );
+
+    if boost_vault_key != *boost_vault_account.key {
+        msg!("Invalid Boost Vault, expected : {}", boost_vault_key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // the vault is only created by the first boost
+    if boost_vault_account.data.borrow().len() == 0 {
+        return Ok(());
+    }
+
+    let stake_seeds = stake.signer_seeds();
+    let vault_balance = Account::unpack(&boost_vault_account.data.borrow())?.amount;
+
+    if vault_balance > 0 {
+        msg!("Unlocking {} boost tokens", vault_balance);
+
+        invoke_signed(
+            &transfer(token_program.key, boost_vault_account.key, user_reward_token_account.key, stake_account.key, &[stake_account.key], vault_balance)?,
+            &[boost_vault_account.clone(), user_reward_token_account.clone(), stake_account.clone(), token_program.clone()],
+            &[&stake_seeds]
+        )?;
+    }
+
+    invoke_signed(
+        &close_account(token_program.key, boost_vault_account.key, user_account.key, stake_account.key, &[stake_account.key])?,
+        &[boost_vault_account.clone(), user_account.clone(), stake_account.clone(), token_program.clone()],
+        &[&stake_seeds]
+    )
+}
+
+/// Pays the SOL revenue deposited since the last payout of the position from the revenue vault of the pool
+/// to the holder and moves the revenue snapshot of the stake to the current index.
+pub fn pay_revenue_share(
+    holder_account: &AccountInfo,
+    revenue_vault_account: &AccountInfo,
+    stake_details: &StakeDetails,
+    stake: &mut Stake,
+) -> ProgramResult {
+
+    let (revenue_amount, revenue_carry) = stake_details.revenue_owed(stake.revenue_index_snapshot, stake.revenue_carry);
+    stake.revenue_index_snapshot = stake_details.revenue_per_share;
+    stake.revenue_carry = revenue_carry;
+
+    if revenue_amount == 0 {
+        return Ok(());
+    }
+
+    if stake_details.revenue_vault != *revenue_vault_account.key {
+        msg!("Invalid Revenue Vault, expected : {}", stake_details.revenue_vault);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if !holder_account.is_writable || !revenue_vault_account.is_writable {
+        msg!("Holder or Revenue Vault Account is not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    msg!("Paying {} lamports of revenue share", revenue_amount);
+
+    let final_vault_lamports = revenue_vault_account.lamports().checked_sub(revenue_amount).ok_or(NftStakingError::InsufficientRevenue)?;
+    let final_holder_lamports = holder_account.lamports().checked_add(revenue_amount).ok_or(NftStakingError::RewardOverflow)?;
+
+    **revenue_vault_account.lamports.borrow_mut() = final_vault_lamports;
+    **holder_account.lamports.borrow_mut() = final_holder_lamports;
+
+    Ok(())
+}
+
+/// Transfers the staked NFT back to the user and closes the stake NFT token account and the stake account,
+/// refunding their rent to the account which paid it at stake time. `stake_seeds` are the signer seeds of the stake account.
+pub fn release_staked_nft<'a>(
+    rent_payer_account: &AccountInfo<'a>,
+    user_nft_token_account: &AccountInfo<'a>,
+    stake_account: &AccountInfo<'a>,
+    stake_nft_token_account: &AccountInfo<'a>,
+    nft_mint_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stake_seeds: &[&[u8]],
+) -> ProgramResult {
+
+    let stake_nft_token_key = get_associated_token_address_with_program_id(
+        stake_account.key, 
+        nft_mint_account.key, 
+        token_program.key
+    );
+
+    if stake_nft_token_key != *stake_nft_token_account.key {
+        msg!("Invalid Stake NFT Token Account , expected : {}", stake_nft_token_key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_nft_token_account_data = Account::unpack(&stake_nft_token_account.data.borrow())?;
+
+    if stake_nft_token_account_data.amount == 0 {
+        msg!("No NFT Found in stake_nft_token_account : {}", stake_nft_token_account.key);
+        return Err(NftStakingError::NoNFTFound.into())
+    }
+
+    let transfer_ix = transfer(
+        token_program.key, 
+        stake_nft_token_account.key, 
+        user_nft_token_account.key, 
+        stake_account.key, 
+        &[stake_account.key], 
+        stake_nft_token_account_data.amount
+    )?;
+
+    msg!("Transferring NFT back to the user");
+
+    invoke_signed(
+        &transfer_ix, 
+        &[
+            stake_account.clone(),
+            stake_nft_token_account.clone(),
+            user_nft_token_account.clone(),
+            token_program.clone(),
+        ], 
+        &[stake_seeds]
+    )?;
+
+    msg!("Closing Stake NFT Account");
+
+    let close_ix = close_account(
+        token_program.key, 
+        stake_nft_token_account.key, 
+        rent_payer_account.key, 
+        stake_account.key, 
+        &[stake_account.key]
+    )?;
+
+    invoke_signed(
+        &close_ix, 
+        &[
+            stake_account.clone(),
+            stake_nft_token_account.clone(),
+            rent_payer_account.clone(),
+            token_program.clone(),
+        ], 
+        &[stake_seeds]
+    )?;
+
+    let stake_account_lamports = stake_account.lamports();
+    let rent_payer_lamports = rent_payer_account.lamports();
+
+    let final_rent_payer_lamports = rent_payer_lamports.checked_add(stake_account_lamports);
+
+    if final_rent_payer_lamports.is_none() {
+        msg!("Lamports Addition Overlfow Error");
+        return Err(NftStakingError::StakeAccountCloseError.into());
+    }
+
+    msg!("Transferring Lamports from Stake Account to the Rent Payer");
+    let mut rent_payer_lamports = rent_payer_account.lamports.borrow_mut();
+    **rent_payer_lamports = final_rent_payer_lamports.unwrap();
+
+    msg!("Resetting Stake Account Lamports to 0");
+    let mut stake_account_lamports = stake_account.lamports.borrow_mut();
+    **stake_account_lamports = 0;
+
+    msg!("Closing Stake Account");
+
+    stake_account.assign(&solana_program::system_program::ID);
+    stake_account.realloc(0, false)?;
+
+    Ok(())
+}
+
+/// Loads the stake of a position and checks that `holder` holds its receipt token.
+/// The receipt, and not the wallet which staked, authorizes everything done with the position.
+pub fn load_stake_for_holder(
+    program_id: &Pubkey,
+    holder: &AccountInfo,
+    holder_receipt_account: &AccountInfo,
+    stake_details_account: &AccountInfo,
+    stake_details: &StakeDetails,
+    stake_account: &AccountInfo,
+    nft_mint_account: &AccountInfo,
+) -> Result<Stake, ProgramError> {
+
+    if stake_account.data.borrow().len() == 0 {
+        msg!("Stake Account is Not Initialized");
+        return Err(NftStakingError::AccountNotInitialized.into());
+    }
+
+    let stake = Stake::try_from_slice(&stake_account.data.borrow())?;
+
+    if stake.stake_details_key != *stake_details_account.key || stake.nft_mint != *nft_mint_account.key {
+        msg!("Stake Account {} does not belong to the pool {} and the NFT {}", stake_account.key, stake_details_account.key, nft_mint_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let stake_account_key = Pubkey::create_program_address(&stake.signer_seeds(), program_id)?;
+
+    if stake_account_key != *stake_account.key {
+        msg!("Invalid Stake Account, expected : {}", stake_account_key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *holder_receipt_account.owner != stake_details.token_program_id() {
+        msg!("Receipt Token Account is not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let receipt_data = Account::unpack(&holder_receipt_account.data.borrow())?;
+
+    if receipt_data.mint != stake.receipt_mint || receipt_data.owner != *holder.key || receipt_data.amount != 1 {
+        msg!("{} does not hold the receipt {} of the stake", holder.key, stake.receipt_mint);
+        return Err(NftStakingError::ReceiptNotHeld.into());
+    }
+
+    Ok(stake)
+}
+
+/// Checks that `reward_token_account` is a token account of the reward mint of the pool.
+pub fn check_reward_token_account(reward_token_account: &AccountInfo, stake_details: &StakeDetails) -> ProgramResult {
+
+    if *reward_token_account.owner != stake_details.token_program_id() {
+        msg!("Reward Token Account is not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let reward_token_data = Account::unpack(&reward_token_account.data.borrow())?;
+
+    if reward_token_data.mint != stake_details.reward_token_mint {
+        msg!("Reward Token Account mint expected : {}, received : {}", stake_details.reward_token_mint, reward_token_data.mint);
+        return Err(NftStakingError::RewardMintMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// Checks that `reward_token_account` is where the rewards of `stake` are paid to for `holder`, see `Stake::reward_destination`.
+/// Points, vesting escrows and guilds are checked when credited.
+pub fn check_reward_destination(reward_token_account: &AccountInfo, holder: &Pubkey, stake: &Stake, stake_details: &StakeDetails) -> ProgramResult {
+
+    if stake_details.is_points_only() || stake_details.is_vesting() || stake.is_guild_position() {
+        return Ok(());
+    }
+
+    let holder_ata = get_associated_token_address_with_program_id(holder, &stake_details.reward_token_mint, &stake_details.token_program_id());
+    let expected_reward_account = stake.reward_destination(holder, holder_ata);
+
+    if expected_reward_account != *reward_token_account.key {
+        msg!("Reward Token Account expected : {}, received : {}", expected_reward_account, reward_token_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    check_reward_token_account(reward_token_account, stake_details)
+}
+
+/// Creates the reward ata of `owner` if it does not exist yet, `payer` pays the rent.
+pub fn create_reward_ata<'a>(
+    payer: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    reward_token_account: &AccountInfo<'a>,
+    reward_mint_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    associated_token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+
+    if !reward_token_account.data_is_empty() {
+        return Ok(());
+    }
+
+    let reward_ata = get_associated_token_address_with_program_id(owner.key, reward_mint_account.key, token_program.key);
+
+    if reward_ata != *reward_token_account.key {
+        msg!("Reward Token Account expected : {}, received : {}", reward_ata, reward_token_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(payer.key, owner.key, reward_mint_account.key, token_program.key),
+        &[
+            payer.clone(),
+            reward_token_account.clone(),
+            owner.clone(),
+            reward_mint_account.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ]
+    )?;
+
+    msg!("Reward token account {} has been created", reward_ata);
+
+    Ok(())
+}
+
+/// Loads the aggregate account of `owner` in the pool and checks its address.
+pub fn load_staker(
+    program_id: &Pubkey,
+    staker_account: &AccountInfo,
+    stake_details_account: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<Staker, ProgramError> {
+
+    if staker_account.owner != program_id || !staker_account.is_writable {
+        msg!("Staker Account is not owned by the program or not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let staker = Staker::try_from_slice(&staker_account.data.borrow())?;
+
+    if staker.stake_details_key != *stake_details_account.key || staker.owner != *owner {
+        msg!("Staker Account {} does not belong to {} in the pool {}", staker_account.key, owner, stake_details_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let staker_account_key = Pubkey::create_program_address(&staker.signer_seeds(), program_id)?;
+
+    if staker_account_key != *staker_account.key {
+        msg!("Invalid Staker Account, expected : {}", staker_account_key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(staker)
+}
+
+/// Creates the aggregate account of a user in the pool, the first time they stake or earn points in it.
+/// `payer` pays the rent, it is the user unless the rent is sponsored.
+pub fn create_staker<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    user: &AccountInfo<'a>,
+    staker_account: &AccountInfo<'a>,
+    stake_details_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    now: i64,
+) -> Result<Staker, ProgramError> {
+
+    let (staker_key, staker_bump) = Pubkey::find_program_address(
+        &[b"staker", stake_details_account.key.as_ref(), user.key.as_ref()],
+        program_id
+    );
+
+    if staker_key != *staker_account.key {
+        msg!("Staker Account expected : {}, received : {}", staker_key, staker_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            staker_account.key,
+            Rent::get()?.minimum_balance(Staker::LEN),
+            Staker::LEN as u64,
+            program_id
+        ),
+        &[payer.clone(), staker_account.clone(), system_program.clone()],
+        &[&[b"staker", stake_details_account.key.as_ref(), user.key.as_ref(), &[staker_bump]]]
+    )?;
+
+    msg!("Staker account has been created");
+
+    Ok(Staker {
+        stake_details_key: *stake_details_account.key,
+        owner: *user.key,
+        staked_count: 0,
+        boost_index: 0,
+        last_update: now,
+        bump_seed: staker_bump,
+        points: 0,
+        referrer: Pubkey::default(),
+        set_bonus_bps: 0,
+        reserved: [0; STAKER_RESERVED_LEN],
+    })
+}
+
+/// Credits `amount` points of a points-only pool to the aggregate account of the holder, creating it if needed.
+/// `system_program` is passed in the slot of the reward mint, which points-only pools do not have.
+pub fn credit_points<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    holder: &AccountInfo<'a>,
+    holder_staker_account: &AccountInfo<'a>,
+    stake_details_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+
+    let mut staker = if holder_staker_account.data.borrow().len() == 0 {
+        create_staker(program_id, payer, holder, holder_staker_account, stake_details_account, system_program, Clock::get()?.unix_timestamp)?
+    } else {
+        load_staker(program_id, holder_staker_account, stake_details_account, holder.key)?
+    };
+
+    staker.points = staker.points.checked_add(amount).ok_or(NftStakingError::RewardOverflow)?;
+    staker.serialize(&mut *holder_staker_account.data.borrow_mut())?;
+
+    msg!("Credited {} points to {}, balance : {}", amount, holder.key, staker.points);
+
+    Ok(())
+}
+
+/// Checks `guild_account` is the guild `guild_key` of the pool and returns its data.
+pub fn load_guild(program_id: &Pubkey, guild_account: &AccountInfo, stake_details_account: &AccountInfo, guild_key: &Pubkey) -> Result<Guild, ProgramError> {
+
+    if guild_account.key != guild_key || guild_account.owner != program_id || !guild_account.is_writable {
+        msg!("Guild Account expected : {}, received : {}", guild_key, guild_account.key);
+        return Err(NftStakingError::InvalidGuild.into());
+    }
+
+    let guild = Guild::try_from_slice(&guild_account.data.borrow())?;
+
+    if guild.stake_details_key != *stake_details_account.key {
+        msg!("Guild {} does not belong to the pool", guild_key);
+        return Err(NftStakingError::InvalidGuild.into());
+    }
+
+    Ok(guild)
+}
+
+/// Credits `amount` rewards of a guild position to the guild, they are split among the members by `ClaimGuildRewards`.
+pub fn credit_guild(program_id: &Pubkey, guild_account: &AccountInfo, stake_details_account: &AccountInfo, guild_key: &Pubkey, amount: u64) -> ProgramResult {
+
+    let mut guild = load_guild(program_id, guild_account, stake_details_account, guild_key)?;
+
+    guild.reward_balance = guild.reward_balance.checked_add(amount).ok_or(NftStakingError::RewardOverflow)?;
+    guild.serialize(&mut *guild_account.data.borrow_mut())?;
+
+    msg!("Credited {} rewards to the guild {}, balance : {}", amount, guild_key, guild.reward_balance);
+
+    Ok(())
+}
+
+/// Checks the vesting escrow of `owner` in the pool and returns its data.
+pub fn load_vesting_escrow(program_id: &Pubkey, vesting_escrow_account: &AccountInfo, stake_details_account: &AccountInfo, owner: &Pubkey) -> Result<VestingEscrow, ProgramError> {
+
+    if vesting_escrow_account.owner != program_id || !vesting_escrow_account.is_writable {
+        msg!("Vesting Escrow Account is not owned by the program or not writable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let vesting_escrow = VestingEscrow::try_from_slice(&vesting_escrow_account.data.borrow())?;
+
+    if vesting_escrow.stake_details_key != *stake_details_account.key || vesting_escrow.owner != *owner {
+        msg!("Vesting Escrow Account does not belong to {} in the pool", owner);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(vesting_escrow)
+}
+
+/// Checks the address of the rent vault of the pool, a system account at ["rent_vault", stake_details] whose lamports
+/// pay the rent of new positions when the pool sponsors it. Returns the bump seed of the vault.
+pub fn check_rent_vault(program_id: &Pubkey, rent_vault_account: &AccountInfo, stake_details_account: &AccountInfo) -> Result<u8, ProgramError> {
+
+    let (rent_vault_key, rent_vault_bump) = Pubkey::find_program_address(
+        &[b"rent_vault", stake_details_account.key.as_ref()],
+        program_id
+    );
+
+    if rent_vault_key != *rent_vault_account.key || !rent_vault_account.is_writable {
+        msg!("Rent Vault expected : {} and writable, received : {}", rent_vault_key, rent_vault_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(rent_vault_bump)
+}
+
+/// Checks that `rent_payer_account` is the account which paid the rent of the position at stake time, the holder
+/// passes itself when it paid it. Positions staked before the rent payer was recorded were paid by their staker and refund the holder.
+pub fn check_rent_payer_account(rent_payer_account: &AccountInfo, holder: &Pubkey, stake: &Stake) -> ProgramResult {
+
+    let expected_rent_payer = if stake.rent_payer == Pubkey::default() { *holder } else { stake.rent_payer };
+
+    if *rent_payer_account.key != expected_rent_payer || !rent_payer_account.is_writable {
+        msg!("Rent Payer Account expected : {} and writable, received : {}", expected_rent_payer, rent_payer_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+/// Burns the receipt of a closed position and closes the receipt token account. Its rent goes back to the rent payer
+/// of the position, which created it, unless the receipt was transferred to another holder or the rent vault of the
+/// pool sponsored the position, the receipt account was then paid by the payer of the stake.
+pub fn burn_receipt<'a>(
+    holder: &AccountInfo<'a>,
+    holder_receipt_account: &AccountInfo<'a>,
+    rent_payer_account: &AccountInfo<'a>,
+    receipt_mint_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stake: &Stake,
+) -> ProgramResult {
+
+    if *receipt_mint_account.key != stake.receipt_mint {
+        msg!("Receipt Mint expected : {}, received : {}", stake.receipt_mint, receipt_mint_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    msg!("Burning the stake receipt");
+
+    invoke(
+        &burn(token_program.key, holder_receipt_account.key, receipt_mint_account.key, holder.key, &[holder.key], 1)?,
+        &[holder_receipt_account.clone(), receipt_mint_account.clone(), holder.clone(), token_program.clone()]
+    )?;
+
+    let rent_destination = if *holder.key == stake.staker && !stake.rent_vault_sponsored { rent_payer_account } else { holder };
+
+    invoke(
+        &close_account(token_program.key, holder_receipt_account.key, rent_destination.key, holder.key, &[holder.key])?,
+        &[holder_receipt_account.clone(), rent_destination.clone(), holder.clone(), token_program.clone()]
+    )
+}
+
+/// Soulbound badge of a pool, a Token-2022 mint with the NonTransferable and PermanentDelegate
+/// extensions controlled by the stake details pda. A wallet holds one badge per staked position.
+pub struct StakingBadge<'a, 'b> {
+    pub token_2022_program: &'b AccountInfo<'a>,
+    pub badge_mint_account: &'b AccountInfo<'a>,
+    pub stake_details_account: &'b AccountInfo<'a>,
+    pub stake_details: &'b StakeDetails,
+}
+
+impl<'a> StakingBadge<'a, '_> {
+
+    fn validate(&self, owner: &Pubkey, owner_badge_account: &AccountInfo<'a>) -> ProgramResult {
+
+        if *self.token_2022_program.key != spl_token_2022::id() {
+            msg!("Token 2022 Program expected : {}, received : {}", spl_token_2022::id(), self.token_2022_program.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if *self.badge_mint_account.key != self.stake_details.badge_mint {
+            msg!("Badge Mint expected : {}, received : {}", self.stake_details.badge_mint, self.badge_mint_account.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let owner_badge_key = get_associated_token_address_with_program_id(
+            owner,
+            self.badge_mint_account.key,
+            self.token_2022_program.key
+        );
+
+        if owner_badge_key != *owner_badge_account.key {
+            msg!("Badge Token Account expected : {}, received : {}", owner_badge_key, owner_badge_account.key);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    /// Mints a badge to the owner, creating the badge token account of the owner if needed with the rent paid by `payer`.
+    pub fn mint_to(
+        &self,
+        payer: &AccountInfo<'a>,
+        owner: &AccountInfo<'a>,
+        owner_badge_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        associated_token_program: &AccountInfo<'a>,
+    ) -> ProgramResult {
+
+        self.validate(owner.key, owner_badge_account)?;
+
+        invoke(
+            &create_associated_token_account_idempotent(payer.key, owner.key, self.badge_mint_account.key, self.token_2022_program.key),
+            &[
+                payer.clone(),
+                owner_badge_account.clone(),
+                owner.clone(),
+                self.badge_mint_account.clone(),
+                system_program.clone(),
+                self.token_2022_program.clone(),
+                associated_token_program.clone(),
+            ]
+        )?;
+
+        let ix = spl_token_2022::instruction::mint_to(
+            self.token_2022_program.key,
+            self.badge_mint_account.key,
+            owner_badge_account.key,
+            self.stake_details_account.key,
+            &[self.stake_details_account.key],
+            1
+        )?;
+
+        msg!("Minting a staking badge to {}", owner.key);
+
+        invoke_signed(
+            &ix,
+            &[
+                self.badge_mint_account.clone(),
+                owner_badge_account.clone(),
+                self.stake_details_account.clone(),
+            ],
+            &[&self.stake_details.signer_seeds()]
+        )
+    }
+
+    /// Burns a badge of the owner, the stake details pda signs as the permanent delegate
+    /// so the position can be closed by someone else than the staker. Nothing is burnt when the
+    /// owner already burnt its badges or closed its badge token account.
+    pub fn burn_from(&self, owner: &Pubkey, owner_badge_account: &AccountInfo<'a>) -> ProgramResult {
+
+        self.validate(owner, owner_badge_account)?;
+
+        // the badge must not lock the position, the staker may not even hold its receipt anymore
+        if owner_badge_account.data_is_empty()
+            || StateWithExtensions::<spl_token_2022::state::Account>::unpack(&owner_badge_account.data.borrow())?.base.amount == 0
+        {
+            msg!("{} holds no staking badge anymore", owner);
+            return Ok(());
+        }
+
+        let ix = spl_token_2022::instruction::burn(
+            self.token_2022_program.key,
+            owner_badge_account.key,
+            self.badge_mint_account.key,
+            self.stake_details_account.key,
+            &[self.stake_details_account.key],
+            1
+        )?;
+
+        msg!("Burning a staking badge of {}", owner);
+
+        invoke_signed(
+            &ix,
+            &[
+                owner_badge_account.clone(),
+                self.badge_mint_account.clone(),
+                self.stake_details_account.clone(),
+            ],
+            &[&self.stake_details.signer_seeds()]
+        )
+    }
+}